@@ -0,0 +1,109 @@
+//! Construction of programs without going through the text parser.
+
+use crate::export::{assemble_lines, ExportError, LogisimProgram};
+use crate::instructions::{Args, FullInstr, Immediate8, ImmediateError, Instr, Reg};
+use crate::parser::ParsedLine;
+
+/// Accumulates the lines of a program, to be assembled by [`ProgramBuilder::build`].
+///
+/// # Examples
+/// ```
+/// use parm_assembler::{Args, FullInstr, Instr, ProgramBuilder, Reg};
+///
+/// let instr = |instr, args| FullInstr { instr, args };
+///
+/// let program = ProgramBuilder::new()
+///     .movs(Reg::R0, 0)
+///     .movs(Reg::R1, 1)
+///     .label(".goto")
+///     .movs(Reg::R2, 20)
+///     .instr(instr(Instr::Cmp, Args::TwoRegs(Reg::R0, Reg::R1)))
+///     .branch_if(Instr::Bmi, ".then1")
+///     .branch(".endif1")
+///     .label(".then1")
+///     .instr(instr(Instr::Rsbs, Args::RdRnImm0(Reg::R2, Reg::R2)))
+///     .label(".endif1")
+///     .instr(instr(Instr::Cmp, Args::TwoRegs(Reg::R2, Reg::R1)))
+///     .branch_if(Instr::Blt, ".then2")
+///     .branch(".endif2")
+///     .label(".then2")
+///     .movs(Reg::R0, 50)
+///     .branch(".goto")
+///     .label(".endif2")
+///     .instr(instr(Instr::Adds, Args::RdRnRm(Reg::R3, Reg::R0, Reg::R2)))
+///     .build()
+///     .unwrap();
+///
+/// let expected = "v2.0 raw\n2000 2101 2214 4288 d4ff e7ff 4252 428a dbff e000 2032 e7f4 1883";
+/// assert_eq!(program.rom, expected);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ProgramBuilder {
+    lines: Vec<ParsedLine>,
+    /// The first invalid operand, reported by `build`
+    error: Option<ImmediateError>,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends any instruction
+    pub fn instr(mut self, instr: FullInstr) -> Self {
+        self.lines.push(ParsedLine::Instr(instr));
+        self
+    }
+
+    /// Appends `movs rd, #imm`
+    pub fn movs(mut self, rd: Reg, imm: u16) -> Self {
+        match Immediate8::new(imm) {
+            Ok(imm) => self.instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RdImm8(rd, imm),
+            }),
+            Err(e) => {
+                self.error.get_or_insert(e);
+                self
+            }
+        }
+    }
+
+    /// Appends an unconditional branch to `label`
+    pub fn branch(self, label: &str) -> Self {
+        self.branch_if(Instr::B, label)
+    }
+
+    /// Appends a branch to `label`, `condition` being one of the branch instructions such as [`Instr::Beq`]
+    pub fn branch_if(self, condition: Instr, label: &str) -> Self {
+        self.instr(FullInstr {
+            instr: condition,
+            args: Args::Label(label.to_owned()),
+        })
+    }
+
+    /// Defines `label` at the current position
+    pub fn label(mut self, label: &str) -> Self {
+        self.lines.push(ParsedLine::Label(label.to_owned()));
+        self
+    }
+
+    /// Appends a string to the RAM, like `.asciz`
+    pub fn string(mut self, text: &str) -> Self {
+        self.lines.push(ParsedLine::String(text.to_owned()));
+        self
+    }
+
+    /// The lines accumulated so far
+    pub fn lines(&self) -> &[ParsedLine] {
+        &self.lines
+    }
+
+    /// Assembles the program, failing on the first invalid operand or unknown label.
+    pub fn build(self) -> Result<LogisimProgram, ExportError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        assemble_lines(self.lines)
+    }
+}
@@ -0,0 +1,257 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::instructions::{Args, FullInstr, Instr};
+use crate::logic::Program;
+use crate::parser::ParsedLine;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum WarningKind {
+    #[error("shift by zero is a move")]
+    ShiftByZero,
+    #[error("{0} of a zero immediate does nothing")]
+    ZeroImmediate(&'static str),
+    #[error("branch to {0} jumps to the next instruction")]
+    BranchToNext(String),
+    #[error("the program may run past its last instruction, end it with a branch")]
+    FallThrough,
+    #[error("unknown directive {0} is ignored")]
+    UnknownDirective(String),
+    #[error("{0} is declared global but never defined")]
+    UndefinedGlobal(String),
+}
+
+/// Selects the optional lints run by [`lint`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LintOptions {
+    /// Warn when the last instruction is not an unconditional branch
+    pub fall_through: bool,
+}
+
+/// A suspicious, yet valid, instruction.
+/// Warnings never change the emitted program.
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[error("instruction {address}: {kind}")]
+pub struct Warning {
+    /// Address of the instruction in the ROM, in words
+    pub address: usize,
+    pub kind: WarningKind,
+}
+
+/// How bad a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A warning or an error about a line of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// File the line is in, if known. It differs from the assembled file when the line was included.
+    pub path: Option<PathBuf>,
+    /// Line of the file, starting at 1, if known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+fn lint_instr(instr: &FullInstr, address: usize, program: &Program) -> Option<WarningKind> {
+    match (&instr.instr, &instr.args) {
+        (Instr::Lsls | Instr::Lsrs | Instr::Asrs, Args::RdRmImm5(_, _, imm)) if imm.0 == 0 => {
+            Some(WarningKind::ShiftByZero)
+        }
+        (Instr::Adds2, Args::RdRnImm3(_, _, imm)) if imm.0 == 0 => {
+            Some(WarningKind::ZeroImmediate("addition"))
+        }
+        (Instr::Subs2, Args::RdRnImm3(_, _, imm)) if imm.0 == 0 => {
+            Some(WarningKind::ZeroImmediate("subtraction"))
+        }
+        (Instr::Adds3, Args::RdImm8(_, imm)) if imm.0 == 0 => {
+            Some(WarningKind::ZeroImmediate("addition"))
+        }
+        (Instr::Subs3, Args::RdImm8(_, imm)) if imm.0 == 0 => {
+            Some(WarningKind::ZeroImmediate("subtraction"))
+        }
+        (_, Args::Label(label)) if program.rom_labels.get(label) == Some(&(address + 1)) => {
+            Some(WarningKind::BranchToNext(label.to_owned()))
+        }
+        _ => None,
+    }
+}
+
+/// Looks for suspicious instructions in an assembled program.
+///
+/// # Arguments
+///
+/// * `lines`: The parsed lines the program was made from.
+/// * `program`: The assembled program.
+/// * `options`: The optional lints to run.
+///
+/// returns: The warnings, in program order.
+pub fn lint(lines: &[ParsedLine], program: &Program, options: &LintOptions) -> Vec<Warning> {
+    let mut address = 0;
+    let mut last = None;
+    let mut warnings = Vec::new();
+    for line in lines {
+        match line {
+            ParsedLine::Instr(instr) => {
+                if let Some(kind) = lint_instr(instr, address, program) {
+                    warnings.push(Warning { address, kind });
+                }
+                last = Some((address, instr));
+                address += 1;
+            }
+            ParsedLine::Org(target) => address = *target,
+            ParsedLine::UnknownDirective(name) => warnings.push(Warning {
+                address,
+                kind: WarningKind::UnknownDirective(name.clone()),
+            }),
+            ParsedLine::Global(label)
+                if !program.rom_labels.contains_key(label)
+                    && !program.ram_labels.contains_key(label) =>
+            {
+                warnings.push(Warning {
+                    address,
+                    kind: WarningKind::UndefinedGlobal(label.clone()),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((address, instr)) = last.filter(|_| options.fall_through) {
+        if instr.instr != Instr::B {
+            warnings.push(Warning {
+                address,
+                kind: WarningKind::FallThrough,
+            });
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::make_program;
+    use crate::parser::parse_lines;
+
+    fn warnings(input: &str) -> Vec<Warning> {
+        let lines = parse_lines(input).unwrap();
+        let program = make_program(lines.clone()).unwrap();
+        lint(&lines, &program, &LintOptions::default())
+    }
+
+    #[test]
+    fn shift_by_zero() {
+        let warnings = warnings("lsls r0, r1, #0");
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                address: 0,
+                kind: WarningKind::ShiftByZero
+            }]
+        );
+        assert_eq!(warnings[0].kind.to_string(), "shift by zero is a move");
+    }
+
+    #[test]
+    fn zero_immediate() {
+        let input = "
+            adds r0, r0, #0
+            subs r1, #0
+            adds r2, #1
+        ";
+        assert_eq!(
+            warnings(input),
+            vec![
+                Warning {
+                    address: 0,
+                    kind: WarningKind::ZeroImmediate("addition")
+                },
+                Warning {
+                    address: 1,
+                    kind: WarningKind::ZeroImmediate("subtraction")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn branch_to_next() {
+        let input = "
+            b .next
+            .next:
+            b .next
+        ";
+        assert_eq!(
+            warnings(input),
+            vec![Warning {
+                address: 0,
+                kind: WarningKind::BranchToNext(".next".to_owned())
+            }]
+        );
+    }
+
+    #[test]
+    fn fall_through() {
+        let options = LintOptions { fall_through: true };
+        let warnings = |input| {
+            let lines = parse_lines(input).unwrap();
+            let program = make_program(lines.clone()).unwrap();
+            lint(&lines, &program, &options)
+        };
+
+        assert_eq!(
+            warnings("movs r0, #1\nadds r0, r1, r2"),
+            vec![Warning {
+                address: 1,
+                kind: WarningKind::FallThrough
+            }]
+        );
+        assert!(warnings(".loop:\nadds r0, r1, r2\nb .loop").is_empty());
+        assert_eq!(warnings(".loop:\nadds r0, #1\nbne .loop").len(), 1);
+        assert!(warnings("").is_empty());
+    }
+
+    #[test]
+    fn unknown_directive() {
+        let input = "
+            .globbl run
+            .p2align 2
+            run:
+            b run
+        ";
+        assert_eq!(
+            warnings(input),
+            vec![Warning {
+                address: 0,
+                kind: WarningKind::UnknownDirective(".globbl".to_owned())
+            }]
+        );
+    }
+
+    #[test]
+    fn undefined_global() {
+        let input = "
+            .globl run
+            .globl main
+            run:
+            b run
+        ";
+        assert_eq!(
+            warnings(input),
+            vec![Warning {
+                address: 0,
+                kind: WarningKind::UndefinedGlobal("main".to_owned())
+            }]
+        );
+    }
+
+    #[test]
+    fn plain_move() {
+        assert!(warnings("movs r0, r1").is_empty());
+    }
+}
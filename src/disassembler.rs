@@ -0,0 +1,77 @@
+//! Decoding of assembled words back into instructions.
+
+use crate::instructions::*;
+
+/// `len` bits of `word`, starting at bit `low`
+fn field(word: u16, low: u8, len: u8) -> u16 {
+    (word >> low) & ((1 << len) - 1)
+}
+
+fn reg(word: u16, low: u8) -> Reg {
+    Reg::try_from(field(word, low, 3) as u8).expect("3 bits registers are r0 to r7")
+}
+
+fn signed<const N: u8>(word: u16) -> SignedImmediate<N, false> {
+    let shift = 16 - N;
+    SignedImmediate(((field(word, 0, N) << shift) as i16) >> shift)
+}
+
+/// The operands of `instr`, laid out as in [`Args::fields`]
+fn decode_args(instr: Instr, word: u16) -> Args {
+    use Instr::*;
+
+    let low = reg(word, 0);
+    match instr {
+        Lsls | Lsrs | Asrs => Args::RdRmImm5(low, reg(word, 3), Immediate(field(word, 6, 5))),
+        Adds | Subs => Args::RdRnRm(low, reg(word, 3), reg(word, 6)),
+        Adds2 | Subs2 => Args::RdRnImm3(low, reg(word, 3), Immediate(field(word, 6, 3))),
+        Adds3 | Subs3 | Movs | Ldr3 | Cmp2 => {
+            Args::RdImm8(reg(word, 8), Immediate(field(word, 0, 8)))
+        }
+        Rsbs => Args::RdRnImm0(low, reg(word, 3)),
+        Movs2 | Ands | Eors | Lsls2 | Lsrs2 | Asrs2 | Adcs | Sbcs | Rors | Tst | Cmp | Cmn
+        | Orrs | Muls | Bics | Mvns | Rev | Rev16 | Revsh | Sxth | Sxtb | Uxth | Uxtb => {
+            Args::TwoRegs(low, reg(word, 3))
+        }
+        Str | Ldr => Args::RtSpImm8W(reg(word, 8), Immediate(field(word, 0, 8))),
+        LdrPc => Args::RtPcImm8W(reg(word, 8), Immediate(field(word, 0, 8))),
+        AddSpReg => Args::RdSpImm8W(reg(word, 8), Immediate(field(word, 0, 8))),
+        Ldr2 => Args::RtRnImm5(low, reg(word, 3), Immediate(field(word, 6, 5))),
+        Strh | Ldrh => Args::RtRnImm5H(low, reg(word, 3), Immediate(field(word, 6, 5))),
+        AddSp | SubSp => Args::Immediate7W(Immediate(field(word, 0, 7))),
+        Wfi | Wfe | Sev => Args::None,
+        Bkpt | Svc => Args::Immediate8(Immediate(field(word, 0, 8))),
+        Beq | Bne | Bcs | Bcc | Bmi | Bpl | Bvs | Bvc | Bhi | Bls | Bge | Blt | Bgt | Ble | Bal => {
+            Args::Immediate8S(signed(word))
+        }
+        B => Args::Immediate11(signed(word)),
+    }
+}
+
+/// Decodes a single assembled word, the reverse of [`FullInstr::encode_u16`].
+///
+/// When several instructions share an encoding, the most specific one is returned:
+/// `lsls rd, rm, #0` is decoded as `movs rd, rm`, and the `ldr rt, label` of a RAM label
+/// as the `movs rt, #offset` it was assembled to.
+///
+/// returns: The instruction, or `None` if the word does not encode a supported instruction.
+///
+/// # Examples
+/// ```
+/// use parm_assembler::disassemble;
+///
+/// assert_eq!(disassemble(0x2001).unwrap().to_string(), "movs r0, #1");
+/// assert!(disassemble(0xffff).is_none());
+/// ```
+pub fn disassemble(word: u16) -> Option<FullInstr> {
+    let bits = BitVec::from_slice(&word.to_be_bytes());
+    let instr = Instr::ALL
+        .into_iter()
+        .filter(|&instr| instr != Instr::Ldr3 && bits.starts_with(&instr.bits()))
+        .max_by_key(|instr| instr.bits().len())?;
+
+    Some(FullInstr {
+        instr,
+        args: decode_args(instr, word),
+    })
+}
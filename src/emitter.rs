@@ -1,13 +1,19 @@
 #![allow(clippy::unusual_byte_groupings)]
 
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
+
 use bitvec::field::BitField;
 
 use crate::instructions::*;
 
+/// Encoding of an instruction, or of a part of it, in the order the CPU expects the bits.
 pub trait ToBinary {
     fn to_binary(&self) -> BitVec;
 }
 
+/// Registers are encoded in 3 bits, only the low bits of `sp` and `pc` are kept.
+/// [`FullInstr::encode_u16`] rejects the instructions where they do not fit.
 impl ToBinary for Reg {
     fn to_binary(&self) -> BitVec {
         let val = *self as u8;
@@ -42,32 +48,45 @@ impl ToBinary for Instr {
     }
 }
 
-impl ToBinary for Args {
-    fn to_binary(&self) -> BitVec {
+impl Args {
+    /// The encoded fields of the arguments, in the order they appear in the instruction.
+    /// Labels and constants have no fields until they are resolved.
+    pub fn fields(&self) -> Vec<BitVec> {
+        self.ordered().into_iter().map(|x| x.to_binary()).collect()
+    }
+
+    fn ordered(&self) -> Vec<&dyn ToBinary> {
         // Each argument set has a different order for the bits.
         // This returns the argument in the correct order.
-        let order: Vec<&dyn ToBinary> = match &self {
+        match &self {
             Args::RdRmImm5(ref rd, ref rm, ref imm5) => vec![imm5, rm, rd],
             Args::RdRnImm3(rd, rn, imm3) => vec![imm3, rn, rd],
-            Args::Label(_) => panic!("Label not resolved"),
-            Args::RtLabel(_, _) => panic!("Label not resolved"),
+            // unresolved operands have no encoding until `FullInstr::complete` resolves them
+            Args::Label(_) | Args::RtLabel(_, _) | Args::RtConstant(_, _) => vec![],
             Args::RdRnRm(rd, rn, rm) => vec![rm, rn, rd],
             Args::RdImm8(rd, imm8) => vec![rd, imm8],
             Args::Immediate7W(imm7w) => vec![imm7w],
             Args::TwoRegs(r1, r2) => vec![r2, r1],
             Args::RdRnImm0(rd, rn) => vec![rn, rd],
+            Args::None => vec![],
+            Args::Immediate8(imm8) => vec![imm8],
             Args::Immediate11(imm11) => vec![imm11],
             Args::RtSpImm8W(rt, imm8w) => vec![rt, imm8w],
+            Args::RtPcImm8W(rt, imm8w) => vec![rt, imm8w],
+            Args::RdSpImm8W(rd, imm8w) => vec![rd, imm8w],
             Args::RtRnImm5(rt, rn, imm5) => vec![imm5, rn, rt],
+            Args::RtRnImm5H(rt, rn, imm5) => vec![imm5, rn, rt],
             Args::Immediate8S(imm8s) => vec![imm8s],
-        };
-        order
-            .into_iter()
-            .map(|x| x.to_binary())
-            .fold(BitVec::new(), |mut acc, x| {
-                acc.extend(x);
-                acc
-            })
+        }
+    }
+}
+
+impl ToBinary for Args {
+    fn to_binary(&self) -> BitVec {
+        self.fields().into_iter().fold(BitVec::new(), |mut acc, x| {
+            acc.extend(x);
+            acc
+        })
     }
 }
 
@@ -79,11 +98,42 @@ impl ToBinary for FullInstr {
     }
 }
 
-impl ToBinary for String {
+impl FullInstr {
+    /// Encodes a single instruction.
+    ///
+    /// returns: The encoding, or `None` if the operands still need to be completed,
+    /// as labels do, or if a register field holds a register above `r7`.
+    ///
+    /// # Examples
+    /// ```
+    /// use parm_assembler::{Args, FullInstr, Immediate8, Instr, Reg, ToBinary};
+    ///
+    /// let instr = FullInstr {
+    ///     instr: Instr::Movs,
+    ///     args: Args::RdImm8(Reg::R0, Immediate8::new(5).unwrap()),
+    /// };
+    ///
+    /// assert_eq!(instr.encode_u16(), Some(0x2005));
+    /// assert_eq!(instr.to_binary().len(), 16);
+    /// ```
+    pub fn encode_u16(&self) -> Option<u16> {
+        if let Args::Label(_) | Args::RtLabel(_, _) | Args::RtConstant(_, _) = self.args {
+            return None;
+        }
+        if self.args.registers().iter().any(|reg| !reg.is_low()) {
+            return None;
+        }
+        let bits = self.to_binary();
+        // operands such as the three registers of `muls` are only valid once completed
+        (bits.len() == 16).then(|| bits.load_be())
+    }
+}
+
+impl ToBinary for [u8] {
     fn to_binary(&self) -> BitVec {
-        // We want each character to be 16 bits since our CPU cannot read byte sized data
-        self.chars()
-            .map(|c| c as u16)
+        // We want each byte to be 16 bits since our CPU cannot read byte sized data
+        self.iter()
+            .map(|&b| b as u16)
             .fold(BitVec::new(), |mut acc, x| {
                 acc.extend(x.to_be_bytes());
                 acc
@@ -91,6 +141,19 @@ impl ToBinary for String {
     }
 }
 
+impl ToBinary for Vec<u8> {
+    fn to_binary(&self) -> BitVec {
+        self.as_slice().to_binary()
+    }
+}
+
+impl ToBinary for String {
+    /// Each UTF-8 byte takes a word: characters above U+007F occupy multiple words
+    fn to_binary(&self) -> BitVec {
+        self.as_bytes().to_binary()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bitvec::bits;
@@ -101,6 +164,27 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn encode_u16() {
+        let unresolved = FullInstr {
+            instr: Instr::B,
+            args: Args::Label("loop".into()),
+        };
+        assert_eq!(unresolved.encode_u16(), None);
+
+        let muls = FullInstr {
+            instr: Instr::Muls,
+            args: Args::RdRnRm(Reg::R0, Reg::R1, Reg::R0),
+        };
+        assert_eq!(muls.encode_u16(), None);
+
+        let high = FullInstr {
+            instr: Instr::Adds,
+            args: Args::RdRnRm(Reg::R0, Reg::SP, Reg::R1),
+        };
+        assert_eq!(high.encode_u16(), None);
+    }
+
     #[test]
     fn reg_to_binary() {
         let reg = Reg::R3;
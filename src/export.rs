@@ -0,0 +1,716 @@
+//! Assembly of text programs into logisim images and other outputs.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bitvec::field::BitField;
+use thiserror::Error;
+
+use crate::diagnostics::{self, Diagnostic, LintOptions, Severity, Warning, WarningKind};
+use crate::emitter::ToBinary;
+use crate::instructions::{Args, BitVec, CompleteError, FullInstr, ImmediateError};
+use crate::json;
+use crate::logic::{
+    expand_pseudo_instructions, make_program, make_program_with_instrs, Program, ProgramOptions,
+    Rom,
+};
+use crate::parser::{self, parse_lines, ParseOptions, ParsedLine, SourceLine};
+use crate::preprocess::{expand_includes_with_locations, IncludeError, SourceLocation};
+use crate::{LOGISIM_ADDRESSED_HEADER, LOGISIM_HEADER};
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ExportError {
+    #[error("Could not complete instruction: {error}")]
+    CompleteError {
+        #[source]
+        error: CompleteError,
+        line: Option<usize>,
+    },
+    #[error("Could not parse input: {0}")]
+    ParseError(#[from] parser::ParseError),
+    #[error("Could not include file: {0}")]
+    IncludeError(#[from] IncludeError),
+    #[error("Invalid operand: {0}")]
+    ImmediateError(#[from] ImmediateError),
+}
+
+impl From<CompleteError> for ExportError {
+    fn from(error: CompleteError) -> Self {
+        ExportError::CompleteError { error, line: None }
+    }
+}
+
+impl ExportError {
+    /// The label the error is about, if any.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            ExportError::CompleteError {
+                error:
+                    CompleteError::LabelNotFound { label, .. }
+                    | CompleteError::JumpTooFar { label, .. }
+                    | CompleteError::RamOffsetTooLarge { label, .. },
+                ..
+            } => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Line of the source the error was found on, starting at 1, if known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ExportError::ParseError(err) => Some(err.line()),
+            ExportError::CompleteError { line, .. } => *line,
+            _ => None,
+        }
+    }
+
+    /// Traces the line of the error back to the file it comes from,
+    /// `locations` being the origin of each line of the assembled source,
+    /// see [`expand_includes_with_locations`].
+    ///
+    /// returns: The location of the line, if known, and the same error about that line.
+    pub fn locate(mut self, locations: &[SourceLocation]) -> (Option<SourceLocation>, Self) {
+        let location = self
+            .line()
+            .and_then(|line| locations.get(line.checked_sub(1)?))
+            .cloned();
+        if let Some(location) = &location {
+            match &mut self {
+                ExportError::ParseError(err) => *err.line_mut() = location.line,
+                ExportError::CompleteError { line, .. } => *line = Some(location.line),
+                _ => {}
+            }
+        }
+        (location, self)
+    }
+}
+
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
+pub struct LogisimProgram {
+    pub rom: String,
+    pub ram: String,
+}
+
+impl LogisimProgram {
+    pub fn with_rom(rom: String) -> Self {
+        Self {
+            rom,
+            ram: LOGISIM_HEADER.trim().to_owned(),
+        }
+    }
+
+    /// The same program, written the way we write it: words separated by a single space,
+    /// lowercase and after the header. Programs that only differ in whitespace and case
+    /// compare equal once normalized.
+    pub fn normalized(&self) -> Self {
+        Self {
+            rom: normalize_logisim(&self.rom),
+            ram: normalize_logisim(&self.ram),
+        }
+    }
+
+    /// Writes the ROM to `{stem}.rom` and the RAM to `{stem}.ram`, in `dir`.
+    pub fn write_to_dir(&self, dir: &Path, stem: &str) -> io::Result<()> {
+        fs::write(dir.join(format!("{stem}.rom")), &self.rom)?;
+        fs::write(dir.join(format!("{stem}.ram")), &self.ram)
+    }
+
+    /// Reads a program written by [`LogisimProgram::write_to_dir`].
+    pub fn read_from_dir(dir: &Path, stem: &str) -> io::Result<Self> {
+        Ok(Self {
+            rom: fs::read_to_string(dir.join(format!("{stem}.rom")))?,
+            ram: fs::read_to_string(dir.join(format!("{stem}.ram")))?,
+        })
+    }
+}
+
+/// The words of an assembled ROM, before they are written in logisim format.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AssembledRom {
+    /// Address of the first word, see [`ProgramOptions::base`]
+    pub base: usize,
+    pub data: Vec<u16>,
+}
+
+impl AssembledRom {
+    fn from_bits(bits: &BitVec, base: usize) -> Self {
+        Self {
+            base,
+            data: bits
+                .chunks(16)
+                .map(|chunk| chunk.load_be::<u16>())
+                .collect(),
+        }
+    }
+
+    /// Each word along with its address, in address order.
+    /// The gaps left by `.org` and the padding of [`ExportOptions::rom_size`] are included.
+    pub fn words(&self) -> impl Iterator<Item = (usize, u16)> + '_ {
+        (self.base..).zip(self.data.iter().copied())
+    }
+}
+
+/// Byte order of the 16 bits words in the logisim image.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+/// Options controlling how a memory is written in logisim format.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConvertOptions {
+    pub endianness: Endianness,
+    /// Write one `address: value` row per word instead of a raw image,
+    /// leaving out the ROM words no instruction was assembled to
+    pub addressed: bool,
+}
+
+fn convert_to_logisim(data: BitVec) -> String {
+    convert_to_logisim_with(data, &ConvertOptions::default())
+}
+
+fn convert_to_logisim_with(data: BitVec, options: &ConvertOptions) -> String {
+    convert_words(data, options, |_| true)
+}
+
+/// Same as [`convert_to_logisim_with`], `written` telling which words belong in an addressed image
+fn convert_words(
+    data: BitVec,
+    options: &ConvertOptions,
+    written: impl Fn(usize) -> bool,
+) -> String {
+    let words = data
+        .chunks(16)
+        .map(|chunk| chunk.load_be::<u16>())
+        .map(|integer| match options.endianness {
+            Endianness::Big => integer,
+            Endianness::Little => integer.swap_bytes(),
+        });
+
+    if options.addressed {
+        return words
+            .enumerate()
+            .filter(|(address, _)| written(*address))
+            .map(|(address, integer)| format!("{address:04x}: {integer:04x}\n"))
+            .fold(LOGISIM_ADDRESSED_HEADER.to_owned(), |acc, row| acc + &row)
+            .trim()
+            .to_owned();
+    }
+
+    let mut out = LOGISIM_HEADER.to_owned();
+    out.reserve(data.len() * 5);
+    words
+        .map(|integer| format!("{integer:04x}"))
+        .fold(out, |acc, i| acc + &i + " ")
+        .trim()
+        .to_owned()
+}
+
+fn normalize_logisim(image: &str) -> String {
+    let tokens: Vec<_> = image.split_whitespace().map(str::to_lowercase).collect();
+    let header: Vec<_> = LOGISIM_HEADER
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect();
+    match tokens.strip_prefix(header.as_slice()) {
+        Some(words) => (LOGISIM_HEADER.to_owned() + &words.join(" "))
+            .trim()
+            .to_owned(),
+        None => tokens.join(" "),
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LogisimError {
+    #[error("Missing the {:?} header", LOGISIM_HEADER.trim())]
+    MissingHeader,
+    #[error("Invalid word {0:?}")]
+    InvalidWord(String),
+}
+
+/// Reads the 16 bits words of a logisim image, such as the ones we write.
+/// Run lengths like `3*0000` are expanded.
+///
+/// # Arguments
+///
+/// * `image`: The content of the image, header included.
+///
+/// returns: The words of the image, in big endian order.
+pub fn parse_logisim(image: &str) -> Result<Vec<u16>, LogisimError> {
+    let words = image
+        .trim_start()
+        .strip_prefix(LOGISIM_HEADER.trim())
+        .ok_or(LogisimError::MissingHeader)?;
+
+    let mut out = Vec::new();
+    for token in words.split_whitespace() {
+        let invalid = || LogisimError::InvalidWord(token.to_owned());
+        let (count, word) = match token.split_once('*') {
+            Some((count, word)) => (count.parse().map_err(|_| invalid())?, word),
+            None => (1, token),
+        };
+        let word = u16::from_str_radix(word, 16).map_err(|_| invalid())?;
+        out.extend(std::iter::repeat_n(word, count));
+    }
+    Ok(out)
+}
+
+/// Assembles the given lines of assembly code into a binary program in logisim format.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+///
+/// returns: A string containing the binary representation of the program, in logisim format.
+pub fn export_to_logisim(input: &str) -> Result<LogisimProgram, ExportError> {
+    export_to_logisim_with(input, None)
+}
+
+/// Same as [`export_to_logisim`], but fails if the program does not fit in the ROM.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+/// * `limit`: The maximum number of instructions the ROM can hold, if any.
+///
+/// returns: A string containing the binary representation of the program, in logisim format.
+pub fn export_to_logisim_with(
+    input: &str,
+    limit: Option<usize>,
+) -> Result<LogisimProgram, ExportError> {
+    export_to_logisim_with_options(
+        input,
+        &ExportOptions {
+            rom_limit: limit,
+            ..Default::default()
+        },
+    )
+}
+
+/// Options for [`export_to_logisim_with_options`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub parse: ParseOptions,
+    pub program: ProgramOptions,
+    /// The maximum number of instructions the ROM can hold, if any
+    pub rom_limit: Option<usize>,
+    /// Number of words the ROM is padded to with [`ProgramOptions::fill`], if any.
+    /// The program must fit in it.
+    pub rom_size: Option<usize>,
+    pub rom: ConvertOptions,
+    pub ram: ConvertOptions,
+    /// The optional lints of [`assemble_with_warnings_with_options`]
+    pub lint: LintOptions,
+}
+
+/// Same as [`export_to_logisim`], with the given options.
+pub fn export_to_logisim_with_options(
+    input: &str,
+    options: &ExportOptions,
+) -> Result<LogisimProgram, ExportError> {
+    assemble_with_warnings_with_options(input, options).map(|(program, _)| program)
+}
+
+/// Same as [`assemble_with_warnings`], with the given options.
+pub fn assemble_with_warnings_with_options(
+    input: &str,
+    options: &ExportOptions,
+) -> Result<(LogisimProgram, Vec<Warning>), ExportError> {
+    assemble_program(input, options).map(|assembly| (assembly.output, assembly.warnings))
+}
+
+/// Everything produced by [`assemble_program`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    pub output: LogisimProgram,
+    /// The laid out program, its ROM not padded to [`ExportOptions::rom_size`]
+    pub program: Program,
+    /// Number of instructions in the ROM, including the ones added by expansions
+    pub instructions: usize,
+    pub warnings: Vec<Warning>,
+}
+
+/// Same as [`assemble_with_warnings_with_options`], but also keeps the laid out program.
+pub fn assemble_program(input: &str, options: &ExportOptions) -> Result<Assembly, ExportError> {
+    let (instrs, program, warnings) = assemble_bits(input, options)?;
+
+    let base = options.program.base;
+    let output = LogisimProgram {
+        rom: convert_words(padded_rom(&program, options), &options.rom, |cell| {
+            instrs.iter().any(|(address, _)| *address - base == cell)
+        }),
+        ram: convert_to_logisim_with(program.ram.clone(), &options.ram),
+    };
+    Ok(Assembly {
+        output,
+        program,
+        instructions: instrs.len(),
+        warnings,
+    })
+}
+
+/// Assembles the given lines of assembly code into the words of the ROM.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+///
+/// returns: The ROM, which can be iterated with [`AssembledRom::words`].
+pub fn export_rom(input: &str) -> Result<AssembledRom, ExportError> {
+    export_rom_with_options(input, &ExportOptions::default())
+}
+
+/// Same as [`export_rom`], with the given options. The conversion options are not used.
+pub fn export_rom_with_options(
+    input: &str,
+    options: &ExportOptions,
+) -> Result<AssembledRom, ExportError> {
+    let (_, program, _) = assemble_bits(input, options)?;
+    Ok(AssembledRom::from_bits(
+        &padded_rom(&program, options),
+        options.program.base,
+    ))
+}
+
+/// The completed instructions, the program and the warnings.
+/// Fails if the program does not fit in the ROM.
+fn assemble_bits(
+    input: &str,
+    options: &ExportOptions,
+) -> Result<(Rom, Program, Vec<Warning>), ExportError> {
+    let source = parser::parse_source_with(input, options.parse)?;
+    let (instrs, program) = complete(&source, &options.program)?;
+    let warnings = lint(&source, &program, options)?;
+
+    let used = program.instrs.len() / 16;
+    let limit = options.rom_limit.into_iter().chain(options.rom_size).min();
+    if let Some(limit) = limit.filter(|&limit| used > limit) {
+        return Err(CompleteError::RomOverflow { used, limit }.into());
+    }
+
+    Ok((instrs, program, warnings))
+}
+
+/// Lays out the parsed lines, the errors pointing at the line of the instruction they are about.
+fn complete(
+    source: &[SourceLine],
+    options: &ProgramOptions,
+) -> Result<(Rom, Program), ExportError> {
+    let lines = source.iter().map(|l| l.line.clone()).collect();
+    make_program_with_instrs(lines, options).map_err(|error| {
+        let instruction = match error {
+            CompleteError::LabelNotFound { instruction, .. }
+            | CompleteError::JumpTooFar { instruction, .. } => instruction,
+            _ => None,
+        };
+        let mut err = ExportError::from(error);
+        // the failing instruction, or else the first one using the label
+        let found = instruction
+            .and_then(|index| {
+                Some(
+                    instruction_sources(source, options)
+                        .ok()?
+                        .get(index)?
+                        .number,
+                )
+            })
+            .or_else(|| {
+                source.iter().find_map(|l| match &l.line {
+                    ParsedLine::Instr(FullInstr {
+                        args: Args::Label(used) | Args::RtLabel(_, used),
+                        ..
+                    }) if Some(used.as_str()) == err.label() => Some(l.number),
+                    _ => None,
+                })
+            });
+        if let ExportError::CompleteError { line, .. } = &mut err {
+            *line = found;
+        }
+        err
+    })
+}
+
+/// Runs the lints on the laid out program, whose addresses account for the expanded instructions
+fn lint(
+    source: &[SourceLine],
+    program: &Program,
+    options: &ExportOptions,
+) -> Result<Vec<Warning>, CompleteError> {
+    let lines = source.iter().map(|l| l.line.clone()).collect();
+    let expanded = expand_pseudo_instructions(lines, &options.program)?;
+    Ok(diagnostics::lint(&expanded, program, &options.lint))
+}
+
+/// The ROM of `program`, padded to [`ExportOptions::rom_size`] if set
+fn padded_rom(program: &Program, options: &ExportOptions) -> BitVec {
+    let mut rom = program.instrs.clone();
+    if let Some(size) = options.rom_size {
+        let fill = BitVec::from_slice(&options.program.fill.to_be_bytes());
+        while rom.len() < size * 16 {
+            rom.extend_from_bitslice(&fill);
+        }
+    }
+    rom
+}
+
+/// Same as [`export_to_logisim`], for lines that were not parsed from text.
+///
+/// # Arguments
+///
+/// * `lines`: The lines of the program, as the parser would produce them.
+///
+/// returns: The program in logisim format.
+pub fn assemble_lines(lines: Vec<ParsedLine>) -> Result<LogisimProgram, ExportError> {
+    let program = make_program(lines)?;
+
+    Ok(LogisimProgram {
+        rom: convert_to_logisim(program.instrs),
+        ram: convert_to_logisim(program.ram),
+    })
+}
+
+/// Same as [`export_to_logisim`], but also looks for suspicious instructions.
+/// The warnings do not affect the emitted program.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+///
+/// returns: The program in logisim format and the warnings found in it.
+pub fn assemble_with_warnings(input: &str) -> Result<(LogisimProgram, Vec<Warning>), ExportError> {
+    assemble_with_warnings_with_options(input, &ExportOptions::default())
+}
+
+/// Everything found while assembling a file, see [`assemble_file`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleReport {
+    /// The assembled program, if there was no error
+    pub program: Option<LogisimProgram>,
+    /// The errors and warnings, in source order
+    pub diagnostics: Vec<Diagnostic>,
+    /// The source of the file, with its includes expanded.
+    /// The diagnostics refer to the lines of the file they were found in, see [`Diagnostic::path`].
+    pub source: String,
+}
+
+impl AssembleReport {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+}
+
+/// Reads and assembles a file, reporting all of its errors and warnings instead of stopping
+/// at the first error. A line that does not parse is skipped so that the next ones are checked too.
+///
+/// # Arguments
+///
+/// * `path`: The file to assemble. Its includes are resolved relative to it.
+///
+/// returns: The program, if there was no error, along with the diagnostics.
+pub fn assemble_file(path: &Path) -> AssembleReport {
+    let source = fs::read_to_string(path)
+        .map_err(|err| IncludeError::Unreadable {
+            path: path.display().to_string(),
+            reason: err.to_string(),
+        })
+        .and_then(|text| {
+            expand_includes_with_locations(&text, path, |file| fs::read_to_string(file))
+        });
+
+    match source {
+        Ok((source, locations)) => assemble_report(source, &locations),
+        Err(err) => AssembleReport {
+            program: None,
+            diagnostics: vec![Diagnostic {
+                severity: Severity::Error,
+                path: Some(path.to_path_buf()),
+                line: None,
+                message: err.to_string(),
+            }],
+            source: String::new(),
+        },
+    }
+}
+
+/// `locations` is the origin of each line of `source`, see [`expand_includes_with_locations`]
+fn assemble_report(source: String, locations: &[SourceLocation]) -> AssembleReport {
+    let location = |line: Option<usize>| line.and_then(|line| locations.get(line.checked_sub(1)?));
+    // the diagnostics along with their line in `source`, to sort them
+    let mut diagnostics = Vec::new();
+    let mut remaining: Vec<_> = source.split('\n').collect();
+    let parsed = loop {
+        match parser::parse_source_with(&remaining.join("\n"), ParseOptions::default()) {
+            Ok(parsed) => break parsed,
+            Err(mut err) => {
+                let line = err.line();
+                let found = location(Some(line));
+                if let Some(found) = found {
+                    *err.line_mut() = found.line;
+                }
+                diagnostics.push((
+                    Some(line),
+                    Diagnostic {
+                        severity: Severity::Error,
+                        path: found.map(|l| l.path.clone()),
+                        line: Some(found.map_or(line, |l| l.line)),
+                        message: err.to_string(),
+                    },
+                ));
+                match line.checked_sub(1).and_then(|i| remaining.get_mut(i)) {
+                    Some(text) if !text.is_empty() => *text = "",
+                    _ => break Vec::new(),
+                }
+            }
+        }
+    };
+
+    let options = ExportOptions::default();
+    let mut program = None;
+    match complete(&parsed, &options.program) {
+        Ok((instrs, assembled)) => {
+            let sources = instruction_sources(&parsed, &options.program).unwrap_or_default();
+            let line_of = |address| {
+                instrs
+                    .iter()
+                    .zip(&sources)
+                    .find(|((a, _), _)| *a == address)
+                    .map(|(_, source)| source.number)
+            };
+            let mut directives = parsed
+                .iter()
+                .filter(|l| matches!(l.line, ParsedLine::UnknownDirective(_)))
+                .map(|l| l.number);
+            for warning in lint(&parsed, &assembled, &options).unwrap_or_default() {
+                let line = match warning.kind {
+                    WarningKind::UnknownDirective(_) => directives.next(),
+                    _ => line_of(warning.address),
+                };
+                let found = location(line);
+                diagnostics.push((
+                    line,
+                    Diagnostic {
+                        severity: Severity::Warning,
+                        path: found.map(|l| l.path.clone()),
+                        line: found.map(|l| l.line).or(line),
+                        message: warning.to_string(),
+                    },
+                ));
+            }
+
+            if diagnostics
+                .iter()
+                .all(|(_, d)| d.severity != Severity::Error)
+            {
+                program = Some(LogisimProgram {
+                    rom: convert_to_logisim(assembled.instrs),
+                    ram: convert_to_logisim(assembled.ram),
+                });
+            }
+        }
+        Err(err) => {
+            let line = err.line();
+            let (found, err) = err.locate(locations);
+            diagnostics.push((
+                line,
+                Diagnostic {
+                    severity: Severity::Error,
+                    path: found.map(|l| l.path),
+                    line: err.line(),
+                    message: err.to_string(),
+                },
+            ))
+        }
+    }
+
+    diagnostics.sort_by_key(|(line, _)| line.unwrap_or(usize::MAX));
+    AssembleReport {
+        program,
+        diagnostics: diagnostics.into_iter().map(|(_, d)| d).collect(),
+        source,
+    }
+}
+
+/// Estimates the number of cycles needed to run each instruction once.
+/// See [`Instr::cycles`](crate::Instr::cycles) for the cost of each instruction.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+///
+/// returns: The sum of the cost of each instruction.
+pub fn estimate_cycles(input: &str) -> Result<usize, ExportError> {
+    let parsed = parse_lines(input)?;
+    Ok(parsed
+        .iter()
+        .filter_map(|line| match line {
+            ParsedLine::Instr(instr) => Some(instr.instr.cycles()),
+            _ => None,
+        })
+        .sum())
+}
+
+/// Assembles the given lines of assembly code into a JSON description of the program.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+///
+/// returns: A JSON object with an `instructions` array, each entry holding the
+/// `address`, `source`, `mnemonic`, `encoding` and `operands` of an instruction,
+/// and a `ram` array holding the RAM words.
+/// Each operand is an object like `{"kind":"reg","value":"r0"}`, of kind `reg`, `imm` or `label`.
+pub fn export_to_json(input: &str) -> Result<String, ExportError> {
+    let source = parser::parse_source_with(input, ParseOptions::default())?;
+    let options = ProgramOptions::default();
+    let (instrs, program) = complete(&source, &options)?;
+
+    let sources = instruction_sources(&source, &options)?;
+    Ok(json::to_json(&sources, &instrs, &program.ram))
+}
+
+/// The source line of each completed instruction, in ROM order.
+/// Every instruction a line is expanded into gets that line.
+fn instruction_sources<'a>(
+    source: &'a [SourceLine],
+    options: &ProgramOptions,
+) -> Result<Vec<&'a SourceLine>, CompleteError> {
+    let mut sources = Vec::new();
+    for line in source
+        .iter()
+        .filter(|l| matches!(l.line, ParsedLine::Instr(_)))
+    {
+        let expanded = expand_pseudo_instructions(vec![line.line.clone()], options)?;
+        sources.extend(std::iter::repeat_n(line, expanded.len()));
+    }
+    Ok(sources)
+}
+
+/// Assembles the given lines of assembly code into a listing.
+/// Each line holds the ROM address, the encoding and the source line of an instruction.
+/// The instructions a source line is expanded into all show that line.
+///
+/// # Arguments
+///
+/// * `input`: A list of ARM instructions, one per line.
+///
+/// returns: The listing, one line per instruction.
+pub fn export_listing(input: &str) -> Result<String, ExportError> {
+    let source = parser::parse_source_with(input, ParseOptions::default())?;
+    let options = ProgramOptions::default();
+    let (instrs, _) = complete(&source, &options)?;
+
+    let mut listing = String::new();
+    for (line, (address, instr)) in instruction_sources(&source, &options)?.iter().zip(instrs) {
+        let word = instr.to_binary().load_be::<u16>();
+        listing += format!("{address:04x}  {word:04x}  {}", line.original).trim_end();
+        listing.push('\n');
+    }
+
+    Ok(listing)
+}
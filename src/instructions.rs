@@ -1,10 +1,17 @@
-use std::collections::HashMap;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
 
 use bitvec::bitvec;
 use bitvec::prelude::Msb0;
+#[cfg(feature = "std")]
 use thiserror::Error;
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
 #[repr(u8)]
 pub enum Reg {
     R0 = 0,
@@ -19,8 +26,50 @@ pub enum Reg {
     SP = 15,
 }
 
+impl Reg {
+    /// Whether the register fits in a 3 bits field
+    pub fn is_low(&self) -> bool {
+        (*self as u8) < 8
+    }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Reg::PC => write!(f, "pc"),
+            Reg::SP => write!(f, "sp"),
+            reg => write!(f, "r{}", *reg as u8),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RegError {
+    /// The register exists but cannot be used by the assembler
+    OutOfRange(u8),
+    /// The operand is encoded in 3 bits, which only fits `r0` to `r7`
+    HighRegister(Reg),
+}
+
+impl Display for RegError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RegError::OutOfRange(number) => write!(
+                f,
+                "register r{number} is not available (only r0-r7, sp, pc)"
+            ),
+            RegError::HighRegister(reg) => {
+                write!(f, "register {reg} cannot be used here (only r0-r7)")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RegError {}
+
 impl TryFrom<u8> for Reg {
-    type Error = ();
+    type Error = RegError;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         for &reg in [
@@ -41,16 +90,46 @@ impl TryFrom<u8> for Reg {
                 return Ok(reg);
             }
         }
-        Err(())
+        Err(RegError::OutOfRange(value))
     }
 }
 
-#[derive(Error, Debug)]
+// Display is written by hand since thiserror is not available without std
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ImmediateError {
-    #[error("Immediate value {0} is too large")]
     TooLarge(i32),
+    /// The value of a word offset is given in bytes but encoded in words
+    OffsetTooLarge {
+        value: u16,
+        max: u16,
+    },
+    /// The value of an offset is given in bytes but is not a whole number of units
+    UnalignedOffset {
+        value: u16,
+        alignment: u16,
+    },
+}
+
+impl Display for ImmediateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ImmediateError::TooLarge(val) => write!(f, "Immediate value {val} is too large"),
+            ImmediateError::OffsetTooLarge { value, max } => write!(
+                f,
+                "Offset of {value} bytes is too large, the maximum is {max} bytes ({} words)",
+                max / 4
+            ),
+            ImmediateError::UnalignedOffset { value, alignment } => write!(
+                f,
+                "Offset of {value} bytes is not a multiple of {alignment} bytes"
+            ),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for ImmediateError {}
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub struct Immediate<const N: u8, const WIDE: bool>(pub u16);
 
@@ -60,19 +139,58 @@ impl<const N: u8, const WIDE: bool> Immediate<N, WIDE> {
     }
 
     const fn upper_bound() -> u16 {
-        let offset = if WIDE { 2 } else { 0 };
-        1 << (N + offset)
+        if WIDE {
+            // the value is stored divided by 4
+            ((1 << N) - 1) * 4
+        } else {
+            (1 << N) - 1
+        }
+    }
+
+    /// The smallest value accepted by [`Immediate::new`]
+    pub const fn min_value() -> u16 {
+        Self::lower_bound()
+    }
+
+    /// The largest value accepted by [`Immediate::new`], in bytes for wide immediates
+    pub const fn max_value() -> u16 {
+        Self::upper_bound()
+    }
+
+    /// The value as written in assembly, in bytes for wide immediates
+    pub const fn value(&self) -> u16 {
+        if WIDE {
+            self.0 * 4
+        } else {
+            self.0
+        }
     }
 
     pub fn new(val: u16) -> Result<Self, ImmediateError> {
-        if val >= Self::lower_bound() && val <= Self::upper_bound() {
+        if WIDE && val % 4 != 0 {
+            Err(ImmediateError::UnalignedOffset {
+                value: val,
+                alignment: 4,
+            })
+        } else if val >= Self::lower_bound() && val <= Self::upper_bound() {
             Ok(Self(if WIDE { val / 4 } else { val }))
+        } else if WIDE {
+            Err(ImmediateError::OffsetTooLarge {
+                value: val,
+                max: Self::upper_bound(),
+            })
         } else {
             Err(ImmediateError::TooLarge(val as i32))
         }
     }
 }
 
+impl<const N: u8, const WIDE: bool> Display for Immediate<N, WIDE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "#{}", self.value())
+    }
+}
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub struct SignedImmediate<const N: u8, const WIDE: bool>(pub i16);
 
@@ -87,7 +205,26 @@ impl<const N: u8, const WIDE: bool> SignedImmediate<N, WIDE> {
         (1 << (N + offset - 1)) - 1
     }
 
-    pub(crate) fn new(val: i16) -> Result<Self, ImmediateError> {
+    /// The smallest value accepted by [`SignedImmediate::new`]
+    pub const fn min_value() -> i16 {
+        Self::lower_bound()
+    }
+
+    /// The largest value accepted by [`SignedImmediate::new`]
+    pub const fn max_value() -> i16 {
+        Self::upper_bound()
+    }
+
+    /// The value as written in assembly, in bytes for wide immediates
+    pub const fn value(&self) -> i16 {
+        if WIDE {
+            self.0 * 4
+        } else {
+            self.0
+        }
+    }
+
+    pub fn new(val: i16) -> Result<Self, ImmediateError> {
         if val >= Self::lower_bound() && val <= Self::upper_bound() {
             Ok(Self(if WIDE { val / 4 } else { val }))
         } else {
@@ -96,6 +233,12 @@ impl<const N: u8, const WIDE: bool> SignedImmediate<N, WIDE> {
     }
 }
 
+impl<const N: u8, const WIDE: bool> Display for SignedImmediate<N, WIDE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "#{}", self.value())
+    }
+}
+
 /// List of all possible instructions
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum Instr {
@@ -110,6 +253,7 @@ pub enum Instr {
     Adds3,
     Subs3,
     Movs,
+    Movs2,
     // Data processing
     Ands,
     Eors,
@@ -133,9 +277,25 @@ pub enum Instr {
     Ldr,
     Ldr2,
     Ldr3,
+    LdrPc,
+    Strh,
+    Ldrh,
     // Misc
     AddSp,
     SubSp,
+    AddSpReg,
+    Rev,
+    Rev16,
+    Revsh,
+    Sxth,
+    Sxtb,
+    Uxth,
+    Uxtb,
+    Wfi,
+    Wfe,
+    Sev,
+    Bkpt,
+    Svc,
     Beq,
     Bne,
     Bcs,
@@ -157,39 +317,152 @@ pub enum Instr {
 pub type BitVec = bitvec::prelude::BitVec<u8, Msb0>;
 
 impl Instr {
-    pub(crate) fn text_instruction(&self) -> &'static [&'static str] {
+    /// Every instruction, in declaration order
+    pub const ALL: [Instr; 66] = [
+        Instr::Lsls,
+        Instr::Lsrs,
+        Instr::Asrs,
+        Instr::Adds,
+        Instr::Subs,
+        Instr::Adds2,
+        Instr::Subs2,
+        Instr::Adds3,
+        Instr::Subs3,
+        Instr::Movs,
+        Instr::Movs2,
+        Instr::Ands,
+        Instr::Eors,
+        Instr::Lsls2,
+        Instr::Lsrs2,
+        Instr::Asrs2,
+        Instr::Adcs,
+        Instr::Sbcs,
+        Instr::Rors,
+        Instr::Tst,
+        Instr::Rsbs,
+        Instr::Cmp,
+        Instr::Cmp2,
+        Instr::Cmn,
+        Instr::Orrs,
+        Instr::Muls,
+        Instr::Bics,
+        Instr::Mvns,
+        Instr::Str,
+        Instr::Ldr,
+        Instr::Ldr2,
+        Instr::Ldr3,
+        Instr::LdrPc,
+        Instr::Strh,
+        Instr::Ldrh,
+        Instr::AddSp,
+        Instr::SubSp,
+        Instr::AddSpReg,
+        Instr::Rev,
+        Instr::Rev16,
+        Instr::Revsh,
+        Instr::Sxth,
+        Instr::Sxtb,
+        Instr::Uxth,
+        Instr::Uxtb,
+        Instr::Wfi,
+        Instr::Wfe,
+        Instr::Sev,
+        Instr::Bkpt,
+        Instr::Svc,
+        Instr::Beq,
+        Instr::Bne,
+        Instr::Bcs,
+        Instr::Bcc,
+        Instr::Bmi,
+        Instr::Bpl,
+        Instr::Bvs,
+        Instr::Bvc,
+        Instr::Bhi,
+        Instr::Bls,
+        Instr::Bge,
+        Instr::Blt,
+        Instr::Bgt,
+        Instr::Ble,
+        Instr::Bal,
+        Instr::B,
+    ];
+
+    /// The instructions that can be written with `mnemonic`, ignoring case.
+    /// A mnemonic often has several encodings, such as `adds`.
+    pub fn from_mnemonic(mnemonic: &str) -> Vec<Instr> {
+        Self::ALL
+            .into_iter()
+            .filter(|instr| {
+                instr
+                    .text_instruction()
+                    .iter()
+                    .any(|text| text.eq_ignore_ascii_case(mnemonic))
+            })
+            .collect()
+    }
+
+    /// Rough number of cycles taken by the instruction, branches being assumed taken.
+    pub fn cycles(&self) -> usize {
+        use Instr::*;
         match self {
-            Instr::Lsls => &["lsls"],
-            Instr::Lsrs => &["lsrs"],
-            Instr::Asrs => &["asrs"],
-            Instr::Adds => &["adds"],
-            Instr::Subs => &["subs"],
+            Str | Ldr | Ldr2 | LdrPc | Strh | Ldrh => 2,
+            Beq | Bne | Bcs | Bcc | Bmi | Bpl | Bvs | Bvc | Bhi | Bls | Bge | Blt | Bgt | Ble
+            | Bal | B => 3,
+            _ => 1,
+        }
+    }
+
+    /// The mnemonics the instruction can be written with, the first one being used for display
+    pub fn text_instruction(&self) -> &'static [&'static str] {
+        match self {
+            Instr::Lsls => &["lsls", "lsl"],
+            Instr::Lsrs => &["lsrs", "lsr"],
+            Instr::Asrs => &["asrs", "asr"],
+            Instr::Adds => &["adds", "add"],
+            Instr::Subs => &["subs", "sub"],
             Instr::Adds2 => &["adds", "add"],
             Instr::Subs2 => &["subs", "sub"],
             Instr::Adds3 => &["adds"],
             Instr::Subs3 => &["subs"],
             Instr::Movs => &["movs"],
+            Instr::Movs2 => &["movs"],
             Instr::Str => &["str"],
             Instr::Ldr => &["ldr"],
             Instr::Ldr2 => &["ldr", "ldrb"],
             Instr::Ldr3 => &["ldr"],
+            Instr::LdrPc => &["ldr"],
+            Instr::Strh => &["strh"],
+            Instr::Ldrh => &["ldrh"],
             Instr::AddSp => &["add"],
             Instr::SubSp => &["sub"],
+            Instr::AddSpReg => &["add"],
+            Instr::Rev => &["rev"],
+            Instr::Rev16 => &["rev16"],
+            Instr::Revsh => &["revsh"],
+            Instr::Sxth => &["sxth"],
+            Instr::Sxtb => &["sxtb"],
+            Instr::Uxth => &["uxth"],
+            Instr::Uxtb => &["uxtb"],
+            Instr::Wfi => &["wfi"],
+            Instr::Wfe => &["wfe"],
+            Instr::Sev => &["sev"],
+            Instr::Bkpt => &["bkpt"],
+            Instr::Svc => &["svc", "swi"],
             Instr::Ands => &["ands"],
             Instr::Eors => &["eors"],
-            Instr::Lsls2 => &["lsls"],
-            Instr::Lsrs2 => &["lsrs"],
-            Instr::Asrs2 => &["asrs"],
+            Instr::Lsls2 => &["lsls", "lsl"],
+            Instr::Lsrs2 => &["lsrs", "lsr"],
+            Instr::Asrs2 => &["asrs", "asr"],
             Instr::Adcs => &["adcs"],
             Instr::Sbcs => &["sbcs"],
-            Instr::Rors => &["rors"],
+            Instr::Rors => &["rors", "ror"],
             Instr::Tst => &["tst"],
-            Instr::Rsbs => &["rsbs"],
+            Instr::Rsbs => &["rsbs", "negs", "neg"],
             Instr::Cmp => &["cmp"],
             Instr::Cmp2 => &["cmp"],
             Instr::Cmn => &["cmn"],
             Instr::Orrs => &["orrs"],
-            Instr::Muls => &["muls"],
+            Instr::Muls => &["muls", "mul"],
             Instr::Bics => &["bics"],
             Instr::Mvns => &["mvns"],
             Instr::B => &["b"],
@@ -224,6 +497,7 @@ impl Instr {
             Subs2 => bitvec![u8, Msb0; 0, 0, 0, 1, 1, 1, 1], // SUBS <Rd > , < Rn> , <#imm3>
             Subs3 => bitvec![u8, Msb0; 0, 0, 1, 1, 1],      // SUBS <Rdn > , #<imm8>
             Movs => bitvec![u8, Msb0; 0, 0, 1, 0, 0],
+            Movs2 => bitvec![u8, Msb0; 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], // lsls <Rd>, <Rm>, #0
             // Data processing
             Ands => bitvec![u8, Msb0; 0, 1, 0, 0, 0, 0, 0, 0, 0, 0],
             Eors => bitvec![u8, Msb0; 0, 1, 0, 0, 0, 0, 0, 0, 0, 1],
@@ -247,9 +521,25 @@ impl Instr {
             Ldr => bitvec![u8, Msb0; 1, 0, 0, 1, 1],
             Ldr2 => bitvec![u8, Msb0; 0, 1, 1, 0, 1],
             Ldr3 => Self::bits(&Movs), // implemented as movs
+            LdrPc => bitvec![u8, Msb0; 0, 1, 0, 0, 1],
+            Strh => bitvec![u8, Msb0; 1, 0, 0, 0, 0],
+            Ldrh => bitvec![u8, Msb0; 1, 0, 0, 0, 1],
             // Misc
             AddSp => bitvec![u8, Msb0; 1, 0, 1, 1, 0, 0, 0, 0, 0],
             SubSp => bitvec![u8, Msb0; 1, 0, 1, 1, 0, 0, 0, 0, 1],
+            AddSpReg => bitvec![u8, Msb0; 1, 0, 1, 0, 1],
+            Rev => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 0, 1, 0, 0, 0],
+            Rev16 => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 0, 1, 0, 0, 1],
+            Revsh => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 0, 1, 0, 1, 1],
+            Sxth => bitvec![u8, Msb0; 1, 0, 1, 1, 0, 0, 1, 0, 0, 0],
+            Sxtb => bitvec![u8, Msb0; 1, 0, 1, 1, 0, 0, 1, 0, 0, 1],
+            Uxth => bitvec![u8, Msb0; 1, 0, 1, 1, 0, 0, 1, 0, 1, 0],
+            Uxtb => bitvec![u8, Msb0; 1, 0, 1, 1, 0, 0, 1, 0, 1, 1],
+            Wfi => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 0, 0, 0, 0],
+            Wfe => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 1, 1, 1, 0, 0, 1, 0, 0, 0, 0, 0],
+            Sev => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 1, 1, 1, 0, 1, 0, 0, 0, 0, 0, 0],
+            Bkpt => bitvec![u8, Msb0; 1, 0, 1, 1, 1, 1, 1, 0],
+            Svc => bitvec![u8, Msb0; 1, 1, 0, 1, 1, 1, 1, 1],
             Beq => bitvec![u8, Msb0; 1, 1, 0, 1, 0, 0, 0, 0],
             Bne => bitvec![u8, Msb0; 1, 1, 0, 1, 0, 0, 0, 1],
             Bcs => bitvec![u8, Msb0; 1, 1, 0, 1, 0, 0, 1, 0],
@@ -283,6 +573,8 @@ pub type Immediate8W = Immediate<8, true>;
 /// List of all possible instructions arguments
 #[derive(PartialEq, Debug, Clone)]
 pub enum Args {
+    None,
+    Immediate8(Immediate8),
     Immediate11(Immediate11),
     Immediate7W(Immediate7W),
     Immediate8S(Immediate8S),
@@ -293,55 +585,184 @@ pub enum Args {
     RdRnImm3(Reg, Reg, Immediate3),
     RdRnRm(Reg, Reg, Reg),
     RtSpImm8W(Reg, Immediate8W),
+    RtPcImm8W(Reg, Immediate8W),
+    RdSpImm8W(Reg, Immediate8W),
     RtRnImm5(Reg, Reg, Immediate5),
+    /// The offset is stored in halfwords
+    RtRnImm5H(Reg, Reg, Immediate5),
     RtLabel(Reg, String),
+    RtConstant(Reg, u32),
     TwoRegs(Reg, Reg),
 }
 
+impl Display for Args {
+    /// Formats the operands as they are written in assembly
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Args::None => Ok(()),
+            Args::Immediate8(imm) => write!(f, "{imm}"),
+            Args::Immediate11(imm) => write!(f, "{imm}"),
+            Args::Immediate7W(imm) => write!(f, "{imm}"),
+            Args::Immediate8S(imm) => write!(f, "{imm}"),
+            Args::Label(label) => write!(f, "{label}"),
+            Args::RdImm8(rd, imm) => write!(f, "{rd}, {imm}"),
+            Args::RdRmImm5(rd, rm, imm) => write!(f, "{rd}, {rm}, {imm}"),
+            Args::RdRnImm0(rd, rn) => write!(f, "{rd}, {rn}, #0"),
+            Args::RdRnImm3(rd, rn, imm) => write!(f, "{rd}, {rn}, {imm}"),
+            Args::RdRnRm(rd, rn, rm) => write!(f, "{rd}, {rn}, {rm}"),
+            Args::RtSpImm8W(rt, imm) => write!(f, "{rt}, [sp, {imm}]"),
+            Args::RtPcImm8W(rt, imm) => write!(f, "{rt}, [pc, {imm}]"),
+            Args::RdSpImm8W(rd, imm) => write!(f, "{rd}, sp, {imm}"),
+            Args::RtRnImm5(rt, rn, imm) => write!(f, "{rt}, [{rn}, {imm}]"),
+            Args::RtRnImm5H(rt, rn, imm) => write!(f, "{rt}, [{rn}, #{}]", imm.0 * 2),
+            Args::RtLabel(rt, label) => write!(f, "{rt}, {label}"),
+            Args::RtConstant(rt, constant) => write!(f, "{rt}, ={constant}"),
+            Args::TwoRegs(r1, r2) => write!(f, "{r1}, {r2}"),
+        }
+    }
+}
+
+impl Args {
+    /// The registers of the operands, each of them is encoded in a 3 bits field
+    pub(crate) fn registers(&self) -> Vec<Reg> {
+        match *self {
+            Args::None
+            | Args::Immediate8(_)
+            | Args::Immediate11(_)
+            | Args::Immediate7W(_)
+            | Args::Immediate8S(_)
+            | Args::Label(_) => vec![],
+            Args::RdImm8(rd, _)
+            | Args::RtSpImm8W(rd, _)
+            | Args::RtPcImm8W(rd, _)
+            | Args::RdSpImm8W(rd, _)
+            | Args::RtLabel(rd, _)
+            | Args::RtConstant(rd, _) => vec![rd],
+            Args::RdRmImm5(r1, r2, _)
+            | Args::RdRnImm0(r1, r2)
+            | Args::RdRnImm3(r1, r2, _)
+            | Args::RtRnImm5(r1, r2, _)
+            | Args::RtRnImm5H(r1, r2, _)
+            | Args::TwoRegs(r1, r2) => vec![r1, r2],
+            Args::RdRnRm(rd, rn, rm) => vec![rd, rn, rm],
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct FullInstr {
     pub instr: Instr,
     pub args: Args,
 }
 
-pub type LabelLookup = HashMap<String, usize>;
+impl Display for FullInstr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.args {
+            Args::None => write!(f, "{}", self.instr.text_instruction()[0]),
+            _ => write!(f, "{} {}", self.instr.text_instruction()[0], self.args),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub type LabelLookup = BTreeMap<String, usize>;
 
+#[cfg(feature = "std")]
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum CompleteError {
-    #[error("Label {0} not found")]
-    LabelNotFound(String),
-    #[error("Label {label} is too far away: {distance}")]
-    JumpTooFar { label: String, distance: i32 },
+    #[error(
+        "Label {label} not found{}",
+        instruction.map(|i| format!(", used by instruction index {i}")).unwrap_or_default()
+    )]
+    LabelNotFound {
+        label: String,
+        /// Index of the instruction using the label among the instructions of the program, if known
+        instruction: Option<usize>,
+    },
+    #[error(
+        "Label {label} is too far away{}: {distance}",
+        instruction.map(|i| format!(" from instruction index {i}")).unwrap_or_default()
+    )]
+    JumpTooFar {
+        label: String,
+        distance: i32,
+        /// Index of the branch among the instructions of the program, if known
+        instruction: Option<usize>,
+    },
     #[error("Invalid instr / arg combination")]
     InvalidArg,
+    #[error("String {0:?} is not preceded by a label")]
+    StringWithoutLabel(String),
+    #[error("String {0:?} cannot be stored in .bss, which only holds zeroed data")]
+    InitializedBss(String),
+    #[error(".space {0} cannot be reserved in .text, the ROM is read-only: use .data or .bss")]
+    SpaceInText(usize),
+    #[error("Operands {0} cannot be resolved for this instruction")]
+    Unresolved(String),
+    #[error("{0} cannot be stored in the RAM")]
+    InvalidRamLine(String),
+    #[error("Program needs {used} ROM words but only {limit} are available")]
+    RomOverflow { used: usize, limit: usize },
+    #[error("Destination {rd} of muls must also be one of its sources, found {rn} and {rm}")]
+    MulsDestination { rd: Reg, rn: Reg, rm: Reg },
+    #[error(
+        "RAM label {label} is at offset {offset}, but ldr rt, label loads offsets into an 8 bits immediate, up to 255"
+    )]
+    RamOffsetTooLarge { label: String, offset: usize },
+    #[error("Cannot move back to address {target} with .org, already at {current}")]
+    OrgBackwards { target: usize, current: usize },
+    #[error("RAM base register {0} must be one of r0-r7")]
+    RamBaseRegister(Reg),
+    #[error("ldr {0}, label cannot load into {0}, which holds the RAM base")]
+    LoadIntoRamBase(Reg),
+    #[error("{} cannot use {reg}, only r0-r7 fit in its register fields", instr.text_instruction()[0])]
+    InvalidRegister { instr: Instr, reg: Reg },
 }
 
+#[cfg(feature = "std")]
 /// Complete the instruction by replacing labels with their actual address
 /// conditional jumps can use 8 bits to encode the distance
-fn complete_label_imm8(label: usize, cur_line: usize) -> Result<Immediate8S, CompleteError> {
-    let offset = label as i16 - cur_line as i16 - 3;
+fn complete_label_imm8(
+    label: &str,
+    addr: usize,
+    cur_line: usize,
+) -> Result<Immediate8S, CompleteError> {
+    let offset = addr as i64 - cur_line as i64 - 3;
+    let too_far = || CompleteError::JumpTooFar {
+        label: label.to_owned(),
+        distance: offset.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        instruction: None,
+    };
 
-    let imm = Immediate8S::new(offset).map_err(|_| CompleteError::JumpTooFar {
-        label: label.to_string(),
-        distance: offset as i32,
-    })?;
+    let offset = i16::try_from(offset).map_err(|_| too_far())?;
+    let imm = Immediate8S::new(offset).map_err(|_| too_far())?;
 
     Ok(imm)
 }
 
+#[cfg(feature = "std")]
 /// Complete the instruction by replacing labels with their actual address
 /// Unconditional jumps can use 11 bits to encode the distance
-fn complete_label_imm11(label: usize, cur_line: usize) -> Result<Immediate11, CompleteError> {
-    let offset = label as i16 - cur_line as i16 - 3;
+fn complete_label_imm11(
+    label: &str,
+    addr: usize,
+    cur_line: usize,
+) -> Result<Immediate11, CompleteError> {
+    let offset = addr as i64 - cur_line as i64 - 3;
+    let too_far = || CompleteError::JumpTooFar {
+        label: label.to_owned(),
+        distance: offset.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        instruction: None,
+    };
 
-    let imm = Immediate11::new(offset).map_err(|_| CompleteError::JumpTooFar {
-        label: label.to_string(),
-        distance: offset as i32,
-    })?;
+    let offset = i16::try_from(offset).map_err(|_| too_far())?;
+    let imm = Immediate11::new(offset).map_err(|_| too_far())?;
 
     Ok(imm)
 }
 
+#[cfg(feature = "std")]
 impl FullInstr {
     /// Complete the instruction by replacing labels with their actual address
     /// and checking that the jump is not too far away
@@ -351,17 +772,27 @@ impl FullInstr {
         rom_labels: &LabelLookup,
         ram_labels: &LabelLookup,
     ) -> Result<FullInstr, CompleteError> {
+        // the emitter would silently truncate them
+        if let Some(reg) = self.args.registers().into_iter().find(|reg| !reg.is_low()) {
+            return Err(CompleteError::InvalidRegister {
+                instr: self.instr,
+                reg,
+            });
+        }
         let mut copy = self.clone();
         if let Args::Label(ref label) = self.args {
             if let Some(&addr) = rom_labels.get(label) {
                 copy.args = match self {
                     FullInstr {
                         instr: Instr::B, ..
-                    } => Args::Immediate11(complete_label_imm11(addr, cur_line)?),
-                    _ => Args::Immediate8S(complete_label_imm8(addr, cur_line)?),
+                    } => Args::Immediate11(complete_label_imm11(label, addr, cur_line)?),
+                    _ => Args::Immediate8S(complete_label_imm8(label, addr, cur_line)?),
                 }
             } else {
-                return Err(CompleteError::LabelNotFound(label.clone()));
+                return Err(CompleteError::LabelNotFound {
+                    label: label.clone(),
+                    instruction: None,
+                });
             }
         }
         if let FullInstr {
@@ -371,18 +802,190 @@ impl FullInstr {
         {
             if let Some(&addr) = ram_labels.get(label) {
                 // so, this is complicated. We are outputting our own ram
-                // we use r7 as the register containing the address of our ram
-                copy.args = Args::RdImm8(
-                    *rt,
-                    Immediate8::new(addr as u16).map_err(|_| CompleteError::JumpTooFar {
-                        label: label.clone(),
-                        distance: addr as i32,
-                    })?,
-                );
+                // rt gets the offset of the label in it, the RAM base register is added afterwards,
+                // see `ProgramOptions::ram_base`
+                let too_far = || CompleteError::RamOffsetTooLarge {
+                    label: label.clone(),
+                    offset: addr,
+                };
+                let addr = u16::try_from(addr).map_err(|_| too_far())?;
+                copy.args = Args::RdImm8(*rt, Immediate8::new(addr).map_err(|_| too_far())?);
             } else {
-                return Err(CompleteError::LabelNotFound(label.clone()));
+                return Err(CompleteError::LabelNotFound {
+                    label: label.clone(),
+                    instruction: None,
+                });
             }
         }
+        // the multiplication is commutative, the destination can be any of the sources
+        if let FullInstr {
+            instr: Instr::Muls,
+            args: Args::RdRnRm(rd, rn, rm),
+        } = *self
+        {
+            copy.args = match (rd == rm, rd == rn) {
+                (true, _) => Args::TwoRegs(rd, rn),
+                (_, true) => Args::TwoRegs(rd, rm),
+                _ => return Err(CompleteError::MulsDestination { rd, rn, rm }),
+            };
+        }
+        // the emitter cannot encode these
+        if let Args::Label(_) | Args::RtLabel(_, _) | Args::RtConstant(_, _) = copy.args {
+            return Err(CompleteError::Unresolved(copy.args.to_string()));
+        }
         Ok(copy)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_immediate_bounds() {
+        assert_eq!(Immediate8W::new(1020).unwrap().0, 255);
+        assert_eq!(Immediate7W::new(508).unwrap().0, 127);
+        assert!(Immediate7W::new(512).is_err());
+    }
+
+    #[test]
+    fn immediate_bounds() {
+        assert_eq!(Immediate8::new(255).unwrap().0, 255);
+        assert!(matches!(
+            Immediate8::new(256),
+            Err(ImmediateError::TooLarge(256))
+        ));
+        assert_eq!(Immediate3::new(7).unwrap().0, 7);
+        assert!(Immediate3::new(8).is_err());
+    }
+
+    #[test]
+    fn opcodes_are_prefix_free() {
+        let shared = [
+            // `ldr rt, label` of a RAM label is assembled as `movs rt, #offset`
+            (Instr::Ldr3, Instr::Movs),
+            // `movs rd, rm` is `lsls rd, rm, #0`
+            (Instr::Lsls, Instr::Movs2),
+        ];
+        let bits = |instr: Instr| {
+            instr
+                .bits()
+                .iter()
+                .map(|b| if *b { '1' } else { '0' })
+                .collect::<String>()
+        };
+
+        let mut collisions = Vec::new();
+        for a in Instr::ALL {
+            for b in Instr::ALL {
+                let expected = shared.contains(&(a, b)) || shared.contains(&(b, a));
+                if a != b && b.bits().starts_with(&a.bits()) && !expected {
+                    collisions.push(format!("{a:?} ({}) prefixes {b:?} ({})", bits(a), bits(b)));
+                }
+            }
+        }
+        assert!(collisions.is_empty(), "{collisions:#?}");
+    }
+
+    #[test]
+    fn from_mnemonic() {
+        assert_eq!(
+            Instr::from_mnemonic("adds"),
+            vec![Instr::Adds, Instr::Adds2, Instr::Adds3]
+        );
+        assert_eq!(Instr::from_mnemonic("MUL"), vec![Instr::Muls]);
+        assert!(Instr::from_mnemonic("nop").is_empty());
+        for instr in Instr::ALL {
+            for mnemonic in instr.text_instruction() {
+                assert!(Instr::from_mnemonic(mnemonic).contains(&instr));
+            }
+        }
+    }
+
+    #[test]
+    fn immediate_ranges() {
+        assert_eq!(Immediate5::min_value(), 0);
+        assert_eq!(Immediate5::max_value(), 31);
+        assert_eq!(Immediate8::max_value(), 255);
+        assert_eq!(Immediate8W::max_value(), 1020);
+        assert_eq!(Immediate7W::max_value(), 508);
+        assert_eq!(Immediate8S::min_value(), -128);
+        assert_eq!(Immediate8S::max_value(), 127);
+        assert_eq!(Immediate11::min_value(), -1024);
+        assert_eq!(Immediate11::max_value(), 1023);
+
+        assert!(Immediate5::new(Immediate5::max_value()).is_ok());
+        assert!(Immediate5::new(Immediate5::max_value() + 1).is_err());
+    }
+
+    #[test]
+    fn wide_immediate_too_large() {
+        let err = Immediate8W::new(2000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Offset of 2000 bytes is too large, the maximum is 1020 bytes (255 words)"
+        );
+    }
+
+    #[test]
+    fn unaligned_wide_immediate() {
+        assert_eq!(
+            Immediate8W::new(6),
+            Err(ImmediateError::UnalignedOffset {
+                value: 6,
+                alignment: 4
+            })
+        );
+        assert_eq!(
+            Immediate7W::new(2).unwrap_err().to_string(),
+            "Offset of 2 bytes is not a multiple of 4 bytes"
+        );
+    }
+
+    #[test]
+    fn reg_display() {
+        assert_eq!(Reg::R0.to_string(), "r0");
+        assert_eq!(Reg::R7.to_string(), "r7");
+        assert_eq!(Reg::SP.to_string(), "sp");
+        assert_eq!(Reg::PC.to_string(), "pc");
+    }
+
+    #[test]
+    fn args_display() {
+        let args = Args::RdRmImm5(Reg::R0, Reg::R1, Immediate5::new(4).unwrap());
+        assert_eq!(args.to_string(), "r0, r1, #4");
+
+        let args = Args::RtSpImm8W(Reg::R2, Immediate8W::new(8).unwrap());
+        assert_eq!(args.to_string(), "r2, [sp, #8]");
+
+        let args = Args::RtRnImm5(Reg::R3, Reg::R4, Immediate5::new(1).unwrap());
+        assert_eq!(args.to_string(), "r3, [r4, #1]");
+
+        let args = Args::RtRnImm5H(Reg::R0, Reg::R1, Immediate5::new(2).unwrap());
+        assert_eq!(args.to_string(), "r0, [r1, #4]");
+
+        let args = Args::RdRnImm0(Reg::R5, Reg::R6);
+        assert_eq!(args.to_string(), "r5, r6, #0");
+
+        let args = Args::Label(".loop".to_owned());
+        assert_eq!(args.to_string(), ".loop");
+
+        let args = Args::Immediate8S(Immediate8S::new(-3).unwrap());
+        assert_eq!(args.to_string(), "#-3");
+    }
+
+    #[test]
+    fn full_instr_display() {
+        let instr = FullInstr {
+            instr: Instr::Adds2,
+            args: Args::RdRnImm3(Reg::R1, Reg::R2, Immediate3::new(3).unwrap()),
+        };
+        assert_eq!(instr.to_string(), "adds r1, r2, #3");
+
+        let instr = FullInstr {
+            instr: Instr::Wfi,
+            args: Args::None,
+        };
+        assert_eq!(instr.to_string(), "wfi");
+    }
+}
@@ -0,0 +1,124 @@
+//! The JSON description of the assembled program.
+
+use bitvec::field::BitField;
+use serde::Serialize;
+
+use crate::emitter::ToBinary;
+use crate::instructions::{Args, BitVec, FullInstr, Reg};
+use crate::parser::SourceLine;
+
+#[derive(Serialize)]
+struct Program<'a> {
+    instructions: Vec<Instruction<'a>>,
+    /// The RAM words, as hexadecimal
+    ram: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Instruction<'a> {
+    address: usize,
+    source: &'a str,
+    mnemonic: &'a str,
+    /// The 16 bits encoding, as hexadecimal
+    encoding: String,
+    operands: Vec<Operand>,
+}
+
+/// An operand, in the order it is written in assembly
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+enum Operand {
+    Reg(String),
+    Imm(i32),
+    Label(String),
+}
+
+impl From<Reg> for Operand {
+    fn from(reg: Reg) -> Self {
+        Operand::Reg(reg.to_string())
+    }
+}
+
+fn imm(value: impl Into<i32>) -> Operand {
+    Operand::Imm(value.into())
+}
+
+fn operands(args: &Args) -> Vec<Operand> {
+    match args {
+        Args::None => vec![],
+        Args::Immediate8(i) => vec![imm(i.value())],
+        Args::Immediate11(i) => vec![imm(i.value())],
+        Args::Immediate7W(i) => vec![imm(i.value())],
+        Args::Immediate8S(i) => vec![imm(i.value())],
+        Args::Label(label) => vec![Operand::Label(label.clone())],
+        Args::RdImm8(rd, i) => vec![(*rd).into(), imm(i.value())],
+        Args::RdRmImm5(rd, rm, i) => vec![(*rd).into(), (*rm).into(), imm(i.value())],
+        Args::RdRnImm0(rd, rn) => vec![(*rd).into(), (*rn).into(), imm(0)],
+        Args::RdRnImm3(rd, rn, i) => vec![(*rd).into(), (*rn).into(), imm(i.value())],
+        Args::RdRnRm(rd, rn, rm) => vec![(*rd).into(), (*rn).into(), (*rm).into()],
+        Args::RtSpImm8W(rt, i) | Args::RdSpImm8W(rt, i) => {
+            vec![(*rt).into(), Reg::SP.into(), imm(i.value())]
+        }
+        Args::RtPcImm8W(rt, i) => vec![(*rt).into(), Reg::PC.into(), imm(i.value())],
+        Args::RtRnImm5(rt, rn, i) => vec![(*rt).into(), (*rn).into(), imm(i.value())],
+        Args::RtRnImm5H(rt, rn, i) => vec![(*rt).into(), (*rn).into(), imm(i.value() * 2)],
+        Args::RtLabel(rt, label) => vec![(*rt).into(), Operand::Label(label.clone())],
+        Args::RtConstant(rt, constant) => vec![(*rt).into(), Operand::Imm(*constant as i32)],
+        Args::TwoRegs(r1, r2) => vec![(*r1).into(), (*r2).into()],
+    }
+}
+
+fn word(bits: &BitVec) -> String {
+    format!("{:04x}", bits.load_be::<u16>())
+}
+
+/// Serializes the completed instructions along with the RAM.
+///
+/// # Arguments
+///
+/// * `sources`: The source line of each instruction.
+/// * `instrs`: The completed instructions along with their address, in ROM order.
+/// * `ram`: The content of the RAM.
+pub fn to_json(sources: &[&SourceLine], instrs: &[(usize, FullInstr)], ram: &BitVec) -> String {
+    let instructions = sources
+        .iter()
+        .zip(instrs)
+        .map(|(source, (address, instr))| Instruction {
+            address: *address,
+            source: &source.original,
+            mnemonic: instr.instr.text_instruction()[0],
+            encoding: word(&instr.to_binary()),
+            operands: operands(&instr.args),
+        })
+        .collect();
+
+    let ram = ram
+        .chunks(16)
+        .map(|chunk| word(&chunk.to_bitvec()))
+        .collect();
+
+    let program = Program { instructions, ram };
+    serde_json::to_string(&program).expect("the program only holds strings and numbers")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{Immediate5, Immediate8W};
+
+    #[test]
+    fn operands_in_assembly_order() {
+        let args = Args::RtSpImm8W(Reg::R1, Immediate8W::new(8).unwrap());
+        assert_eq!(
+            operands(&args),
+            [
+                Operand::Reg("r1".to_string()),
+                Operand::Reg("sp".to_string()),
+                Operand::Imm(8)
+            ]
+        );
+
+        let args = Args::RtRnImm5H(Reg::R0, Reg::R2, Immediate5::new(3).unwrap());
+        assert_eq!(operands(&args)[2], Operand::Imm(6));
+    }
+}
@@ -33,10 +33,31 @@
 //!         assert_eq!(expected, output.rom);
 //! ```
 //!
+//! Instructions can also be built and encoded directly:
+//! ```
+//! use bitvec::field::BitField;
+//! use parm_assembler::{Args, FullInstr, Immediate8, Instr, Reg, ToBinary};
+//!
+//! let instr = FullInstr {
+//!     instr: Instr::Movs,
+//!     args: Args::RdImm8(Reg::R0, Immediate8::new(1).unwrap()),
+//! };
+//!
+//! assert_eq!(instr.to_string(), "movs r0, #1");
+//! assert_eq!(instr.to_binary().load_be::<u16>(), 0x2001);
+//! ```
+//!
 //! # Supported instructions
 //!
 //! See the [assignment](https://bitbucket.org/edge-team-leat/parm_public/src/21ae509e77e4e70bc79301eb59c3f1f9567fb62e/doc/main.pdf) for a full list of supported instructions.
 //!
+//! A few instructions without an encoding are expanded, using `r6` as a scratch register:
+//! - `ldrb rt, [rn, rm]` becomes `adds r6, rn, rm` then `ldrb rt, [r6]`.
+//! - `ands`, `orrs` and `eors` with an immediate, like `ands r0, #0x0f`, become
+//!   `movs r6, #0x0f` then `ands r0, r6`. The destination cannot be `r6`.
+//! - `ldr rt, =<constant>` stores the constant in a literal pool after the initialized RAM data,
+//!   then becomes `ldr rt, <pool entry>` and `ldr rt, [rt]`.
+//!
 //! # Overview
 //!
 //! - Each instruction is an enum variant.
@@ -45,71 +66,66 @@
 //! - Lines are parsed into a vector of `Instruction`s using the `nom` crate.
 //! - The `Instruction`s are then converted into a byte vector using the `bitvec` crate, each one being 16 bits long.
 //! - The byte vector is then converted into a string of hexadecimal numbers.
+//!
+//! # Features
+//!
+//! - `std` (default): the text parser and the logisim export.
+//!   Without it, the crate is `no_std` and only encodes instructions, through [`ToBinary`].
 
-use bitvec::field::BitField;
-use thiserror::Error;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use crate::instructions::{BitVec, CompleteError};
-pub use crate::logic::make_program;
-pub use crate::parser::parse_lines;
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub use crate::builder::ProgramBuilder;
+#[cfg(feature = "std")]
+pub use crate::diagnostics::{Diagnostic, LintOptions, Severity, Warning, WarningKind};
+pub use crate::disassembler::disassemble;
+pub use crate::emitter::ToBinary;
+#[cfg(feature = "std")]
+pub use crate::export::*;
+#[cfg(feature = "std")]
+pub use crate::instructions::CompleteError;
+pub use crate::instructions::{
+    Args, BitVec, FullInstr, Immediate, Immediate11, Immediate3, Immediate5, Immediate7W,
+    Immediate8, Immediate8S, Immediate8W, ImmediateError, Instr, Reg, RegError, SignedImmediate,
+};
+#[cfg(feature = "std")]
+pub use crate::logic::{
+    make_program, make_program_with, ram_strings, Program, ProgramOptions, RamString,
+};
+#[cfg(feature = "std")]
+pub use crate::parser::{
+    parse_lines, parse_lines_strict, parse_lines_with, ParseError, ParseOptions, ParsedLine,
+    Section,
+};
+#[cfg(feature = "std")]
+pub use crate::preprocess::{
+    expand_includes, expand_includes_with_locations, IncludeError, PreprocessError, SourceLocation,
+    MAX_INCLUDE_DEPTH,
+};
+
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod diagnostics;
+mod disassembler;
 mod emitter;
+#[cfg(feature = "std")]
+mod export;
 mod instructions;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
 mod logic;
+#[cfg(feature = "std")]
 mod parser;
+#[cfg(feature = "std")]
+mod preprocess;
+#[cfg(feature = "std")]
 mod utils;
 
 pub const LOGISIM_HEADER: &str = "v2.0 raw\n";
-
-#[derive(Error, Debug)]
-pub enum ExportError {
-    #[error("Could not complete instruction: {0}")]
-    CompleteError(#[from] CompleteError),
-    #[error("Could not parse input: {0}")]
-    ParseError(#[from] parser::ParseError),
-}
-
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
-pub struct LogisimProgram {
-    pub rom: String,
-    pub ram: String,
-}
-
-impl LogisimProgram {
-    pub fn with_rom(rom: String) -> Self {
-        Self {
-            rom,
-            ram: LOGISIM_HEADER.trim().to_owned(),
-        }
-    }
-}
-
-fn convert_to_logisim(data: BitVec) -> String {
-    let mut out = LOGISIM_HEADER.to_owned();
-    out.reserve(data.len() * 5);
-
-    data.chunks(16)
-        .into_iter()
-        .map(|chunk| chunk.load_be::<u16>())
-        .map(|integer| format!("{integer:04x}"))
-        .fold(out, |acc, i| acc + &i + " ")
-        .trim()
-        .to_owned()
-}
-
-/// Assembles the given lines of assembly code into a binary program in logisim format.
-///
-/// # Arguments
-///
-/// * `input`: A list of ARM instructions, one per line.
-///
-/// returns: A string containing the binary representation of the program, in logisim format.
-pub fn export_to_logisim(input: &str) -> Result<LogisimProgram, ExportError> {
-    let parsed = parse_lines(input)?;
-    let program = make_program(parsed)?;
-
-    Ok(LogisimProgram {
-        rom: convert_to_logisim(program.instrs),
-        ram: convert_to_logisim(program.ram),
-    })
-}
+/// Header of the images written with [`ConvertOptions::addressed`]
+#[cfg(feature = "std")]
+pub const LOGISIM_ADDRESSED_HEADER: &str = "v3.0 hex words addressed\n";
@@ -1,28 +1,57 @@
+use std::collections::BTreeMap;
 use std::mem;
-use thiserror::Error;
+
+use bitvec::field::BitField;
 
 use crate::emitter::ToBinary;
 use crate::instructions;
-use crate::instructions::{BitVec, CompleteError, FullInstr, LabelLookup};
-use crate::parser::ParsedLine;
+use crate::instructions::{
+    Args, BitVec, CompleteError, FullInstr, Immediate, Instr, LabelLookup, Reg,
+};
+use crate::parser::{ParsedLine, Section};
+
+/// Computes the ROM address of each line, in words, starting at `base`.
+/// A label has the address of the instruction after it.
+fn rom_addresses(instrs: &[ParsedLine], base: usize) -> Result<Vec<usize>, CompleteError> {
+    let mut current = base;
+    instrs
+        .iter()
+        .map(|line| {
+            let address = current;
+            match line {
+                ParsedLine::Instr(_) => current += 1,
+                ParsedLine::Org(target) if *target < current => {
+                    return Err(CompleteError::OrgBackwards {
+                        target: *target,
+                        current,
+                    })
+                }
+                ParsedLine::Org(target) => current = *target,
+                _ => {}
+            }
+            Ok(address)
+        })
+        .collect()
+}
 
 /// Maps labels to their addresses.
 /// The address of a label is the address of the instruction after the label.
-fn calculate_labels(instrs: &[ParsedLine], ram: &[ParsedLine]) -> (LabelLookup, LabelLookup) {
+fn calculate_labels(
+    instrs: &[ParsedLine],
+    addresses: &[usize],
+    ram: &[ParsedLine],
+) -> Result<(LabelLookup, LabelLookup), CompleteError> {
     let rom_labels = instrs
         .iter()
-        .enumerate()
-        .filter_map(|(i, l)| match l {
-            ParsedLine::Label(l) => Some((i, l.to_owned())),
+        .zip(addresses)
+        .filter_map(|(l, &address)| match l {
+            ParsedLine::Label(l) => Some((l.to_owned(), address)),
             _ => None,
         })
-        .enumerate()
-        // this is a bit tricky: labels do not have an address on their own
-        // so we need to substract current label index
-        .map(|(label_i, (i, l))| (l, i - label_i))
         .collect();
 
     // RAM labels are a bit different: they need to account for string size
+    // each UTF-8 byte is stored in its own word, so the size is counted in bytes
     let mut ram_labels = LabelLookup::new();
     let mut prev_string_end = 0;
 
@@ -34,51 +63,161 @@ fn calculate_labels(instrs: &[ParsedLine], ram: &[ParsedLine]) -> (LabelLookup,
             ParsedLine::String(string) => {
                 prev_string_end += string.len();
             }
-            _ => unreachable!("RAM should only contain labels and strings"),
+            ParsedLine::Space(size) => {
+                prev_string_end += size;
+            }
+            ParsedLine::Literal(_) => {
+                prev_string_end += LITERAL_SIZE;
+            }
+            // RAM should only contain labels and strings
+            other => return Err(CompleteError::InvalidRamLine(format!("{other:?}"))),
         }
     }
-    (rom_labels, ram_labels)
-}
-
-#[derive(Error, Debug)]
-pub enum ProgramError {
-    #[error("Could not complete instruction: {0}")]
-    CompleteError(#[from] CompleteError),
+    Ok((rom_labels, ram_labels))
 }
 
-fn extract_ram(instrs: &mut Vec<ParsedLine>) -> Vec<ParsedLine> {
-    // strings are located after a label
-    // so we need to find label immediately before a string
-    let mut ram = Vec::new();
+/// Moves the RAM lines out of `instrs`, returning the initialized data and the `.bss` lines.
+fn extract_ram(
+    instrs: &mut Vec<ParsedLine>,
+) -> Result<(Vec<ParsedLine>, Vec<ParsedLine>), CompleteError> {
+    // labels in a data section always belong to the RAM
+    // in the text section, strings are located after a label
+    // so we need to find the labels before a string
+    // a string directly following another one belongs to the same label
+    let mut data = Vec::new();
+    let mut bss = Vec::new();
     let mut last_labels = Vec::new();
     let mut to_remove = Vec::new();
+    let mut section = Section::Text;
+    let mut after_string = false;
 
     for (i, instr) in instrs.iter().enumerate() {
+        let ram = if section == Section::Bss {
+            &mut bss
+        } else {
+            &mut data
+        };
         match instr {
+            ParsedLine::Section(new_section) => {
+                section = *new_section;
+                last_labels.clear();
+                to_remove.push(i);
+            }
+            ParsedLine::Label(string) if section != Section::Text => {
+                ram.push(ParsedLine::Label(string.to_owned()));
+                to_remove.push(i);
+            }
             ParsedLine::Label(string) => {
                 last_labels.push((i, string));
             }
+            ParsedLine::String(string) if section == Section::Bss => {
+                return Err(CompleteError::InitializedBss(string.clone()));
+            }
             ParsedLine::String(string) => {
-                if !last_labels.is_empty() {
-                    for (i, label) in mem::take(&mut last_labels).into_iter() {
-                        ram.push(ParsedLine::Label(label.to_owned()));
-                        to_remove.push(i);
-                    }
-                    ram.push(ParsedLine::String(string.clone()));
+                if last_labels.is_empty() && section == Section::Text && !after_string {
+                    return Err(CompleteError::StringWithoutLabel(string.clone()));
+                }
+                for (i, label) in mem::take(&mut last_labels).into_iter() {
+                    ram.push(ParsedLine::Label(label.to_owned()));
                     to_remove.push(i);
-                } else {
-                    panic!("String without label: {}", string);
                 }
+                ram.push(ParsedLine::String(string.clone()));
+                to_remove.push(i);
+            }
+            ParsedLine::Space(size) if section == Section::Text => {
+                return Err(CompleteError::SpaceInText(*size));
+            }
+            ParsedLine::Space(size) => {
+                for (i, label) in mem::take(&mut last_labels).into_iter() {
+                    ram.push(ParsedLine::Label(label.to_owned()));
+                    to_remove.push(i);
+                }
+                ram.push(ParsedLine::Space(*size));
+                to_remove.push(i);
             }
+            // an ignored line does not separate a label from its string
+            ParsedLine::UnknownDirective(_) => continue,
             _ => last_labels.clear(),
         }
+        after_string = matches!(instr, ParsedLine::String(_));
     }
 
     for i in to_remove.iter().rev() {
         instrs.remove(*i);
     }
 
-    ram
+    Ok((data, bss))
+}
+
+/// Size of a literal pool constant in the RAM, one word per byte
+const LITERAL_SIZE: usize = 4;
+
+/// Replaces each `ldr rt, =<constant>` with the load of its pool entry:
+/// `ldr rt, =<constant>` gets the address of the entry, like `ldr rt, label`,
+/// then `ldr rt, [rt]` loads the constant. The entry is allocated by [`allocate_literals`].
+fn expand_constants(instrs: Vec<ParsedLine>) -> Vec<ParsedLine> {
+    instrs
+        .into_iter()
+        .flat_map(|line| match line {
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Ldr3,
+                args: Args::RtConstant(rt, _),
+            }) => vec![
+                line,
+                ParsedLine::Instr(FullInstr {
+                    instr: Instr::Ldr2,
+                    args: Args::RtRnImm5(rt, rt, Immediate(0)),
+                }),
+            ],
+            line => vec![line],
+        })
+        .collect()
+}
+
+/// The label of the pool entry at `offset`.
+/// `=` cannot appear in a label definition, so this never collides.
+fn pool_label(offset: usize) -> String {
+    format!("=pool{offset}")
+}
+
+/// Moves the constants of `ldr rt, =<constant>` to a literal pool at the end of `ram`.
+/// The instruction is rewritten to `ldr rt, <pool label>`, completing like any RAM label.
+/// Identical constants share the same pool entry.
+fn allocate_literals(instrs: &mut [ParsedLine], ram: &mut Vec<ParsedLine>) {
+    // offset of each constant in the pool
+    let mut pool = BTreeMap::new();
+
+    for instr in instrs.iter_mut() {
+        if let ParsedLine::Instr(FullInstr {
+            instr: Instr::Ldr3,
+            args,
+        }) = instr
+        {
+            if let Args::RtConstant(rt, constant) = *args {
+                let pool_size = pool.len() * LITERAL_SIZE;
+                let offset = *pool.entry(constant).or_insert_with(|| {
+                    ram.push(ParsedLine::Label(pool_label(pool_size)));
+                    ram.push(ParsedLine::Literal(constant));
+                    pool_size
+                });
+
+                *args = Args::RtLabel(rt, pool_label(offset));
+            }
+        }
+    }
+}
+
+/// Removes the `.globl` declarations, returning the declared labels.
+fn extract_globals(instrs: &mut Vec<ParsedLine>) -> Vec<String> {
+    let mut globals = Vec::new();
+    instrs.retain(|line| match line {
+        ParsedLine::Global(label) => {
+            globals.push(label.to_owned());
+            false
+        }
+        _ => true,
+    });
+    globals
 }
 
 /// Replaces ldr rt, label with ldr rt, another label
@@ -118,31 +257,55 @@ fn collapse_long(instrs: &mut Vec<ParsedLine>) {
     }
 }
 
+/// Completed instructions along with their address, in ROM order.
+pub(crate) type Rom = Vec<(usize, FullInstr)>;
+
+/// Completes the instructions, returning each of them along with its address.
 fn process_lines(
-    mut instrs: Vec<ParsedLine>,
+    instrs: &[ParsedLine],
+    addresses: &[usize],
     ram: &[ParsedLine],
-) -> Result<(Vec<FullInstr>, Vec<String>), CompleteError> {
-    collapse_long(&mut instrs);
-
-    let (rom_labels, ram_labels) = calculate_labels(&instrs, ram);
-
+    rom_labels: &LabelLookup,
+    ram_labels: &LabelLookup,
+) -> Result<(Rom, Vec<Vec<u8>>), CompleteError> {
     let only_instrs = instrs
-        .iter_mut()
-        .filter_map(|l| match l {
-            ParsedLine::Instr(i) => Some(i),
+        .iter()
+        .zip(addresses)
+        .filter_map(|(l, &address)| match l {
+            ParsedLine::Instr(i) => Some((address, i)),
             _ => None,
         })
         .enumerate()
-        .map(|(i, instr)| instr.complete(i, &rom_labels, &ram_labels))
+        .map(|(index, (address, instr))| {
+            instr
+                .complete(address, rom_labels, ram_labels)
+                .map(|instr| (address, instr))
+                .map_err(|err| match err {
+                    CompleteError::LabelNotFound { label, .. } => CompleteError::LabelNotFound {
+                        label,
+                        instruction: Some(index),
+                    },
+                    CompleteError::JumpTooFar {
+                        label, distance, ..
+                    } => CompleteError::JumpTooFar {
+                        label,
+                        distance,
+                        instruction: Some(index),
+                    },
+                    err => err,
+                })
+        })
         .collect::<Result<_, _>>()?;
 
     let ram = ram
         .iter()
         .filter_map(|l| match l {
-            ParsedLine::String(s) => Some(s),
+            ParsedLine::String(s) => Some(s.as_bytes().to_vec()),
+            ParsedLine::Space(size) => Some(vec![0; *size]),
+            // stored little-endian, one byte per word
+            ParsedLine::Literal(constant) => Some(constant.to_le_bytes().to_vec()),
             _ => None,
         })
-        .map(|s| s.to_owned())
         .collect();
 
     Ok((only_instrs, ram))
@@ -152,24 +315,183 @@ fn process_lines(
 pub struct Program {
     pub instrs: BitVec,
     pub ram: BitVec,
+    /// Address of each label in the ROM, in words, starting at the base address
+    pub rom_labels: LabelLookup,
+    /// Address of each label in the RAM
+    pub ram_labels: LabelLookup,
+    /// Labels declared with `.globl` and their address in the ROM
+    pub entry_points: Vec<(String, usize)>,
+}
+
+impl Program {
+    /// The content of the RAM, each byte being stored in the low byte of its own word.
+    pub fn ram_bytes(&self) -> Vec<u8> {
+        self.ram
+            .chunks(16)
+            .map(|word| word.load_be::<u16>() as u8)
+            .collect()
+    }
 }
 
-pub fn make_program(mut instrs: Vec<ParsedLine>) -> Result<Program, CompleteError> {
-    let ram = extract_ram(&mut instrs);
+/// Options controlling how a program is laid out.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProgramOptions {
+    /// Address of the first instruction in the ROM, in words
+    pub base: usize,
+    /// Word written in the gaps left by `.org`, `0x0000` being `lsls r0, r0, #0`
+    pub fill: u16,
+    /// Register holding the address of the RAM, if it does not start at 0.
+    /// `ldr rt, label` then becomes `movs rt, #offset` followed by `adds rt, rt, base`.
+    /// `rt` cannot be the base register.
+    pub ram_base: Option<Reg>,
+}
+
+/// A string stored in the RAM, see [`ram_strings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamString {
+    /// The labels of the string, empty if it directly follows another string
+    pub labels: Vec<String>,
+    /// Address of the string in the RAM, in bytes
+    pub offset: usize,
+    pub text: String,
+}
 
-    let (rom, ram) = process_lines(instrs, &ram)?;
+/// Lists the strings of a program, in RAM order. `.bss` is left out.
+pub fn ram_strings(mut instrs: Vec<ParsedLine>) -> Result<Vec<RamString>, CompleteError> {
+    let (ram, _) = extract_ram(&mut instrs)?;
+    let (_, ram_labels) = calculate_labels(&[], &[], &ram)?;
 
-    let rom = rom.into_iter().fold(BitVec::new(), |mut acc, instr| {
+    let mut strings = Vec::new();
+    let mut labels = Vec::new();
+    let mut offset = 0;
+    for line in ram {
+        match line {
+            ParsedLine::Label(label) => {
+                offset = ram_labels[&label];
+                labels.push(label);
+            }
+            ParsedLine::String(text) => {
+                let len = text.len();
+                strings.push(RamString {
+                    labels: mem::take(&mut labels),
+                    offset,
+                    text,
+                });
+                offset += len;
+            }
+            ParsedLine::Space(size) => offset += size,
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+/// Replaces the instructions that do not exist in hardware with the ones they are made of.
+/// The addresses of the returned lines are the final ones.
+pub(crate) fn expand_pseudo_instructions(
+    instrs: Vec<ParsedLine>,
+    options: &ProgramOptions,
+) -> Result<Vec<ParsedLine>, CompleteError> {
+    add_ram_base(expand_constants(instrs), options.ram_base)
+}
+
+/// Adds `adds rt, rt, base` after each load of a RAM label, see [`ProgramOptions::ram_base`].
+fn add_ram_base(
+    instrs: Vec<ParsedLine>,
+    base: Option<Reg>,
+) -> Result<Vec<ParsedLine>, CompleteError> {
+    let Some(base) = base else {
+        return Ok(instrs);
+    };
+    if !base.is_low() {
+        return Err(CompleteError::RamBaseRegister(base));
+    }
+
+    let mut expanded = Vec::with_capacity(instrs.len());
+    for line in instrs {
+        let rt = match line {
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Ldr3,
+                args: Args::RtLabel(rt, _) | Args::RtConstant(rt, _),
+            }) => Some(rt),
+            _ => None,
+        };
+        expanded.push(line);
+        if let Some(rt) = rt {
+            // the offset would overwrite the base, which would then be added to itself
+            if rt == base {
+                return Err(CompleteError::LoadIntoRamBase(rt));
+            }
+            expanded.push(ParsedLine::Instr(FullInstr {
+                instr: Instr::Adds,
+                args: Args::RdRnRm(rt, rt, base),
+            }));
+        }
+    }
+    Ok(expanded)
+}
+
+pub fn make_program(instrs: Vec<ParsedLine>) -> Result<Program, CompleteError> {
+    make_program_with(instrs, &ProgramOptions::default())
+}
+
+/// Same as [`make_program`], with the given options.
+pub fn make_program_with(
+    instrs: Vec<ParsedLine>,
+    options: &ProgramOptions,
+) -> Result<Program, CompleteError> {
+    make_program_with_instrs(instrs, options).map(|(_, program)| program)
+}
+
+/// Same as [`make_program_with`], but also returns the completed instructions
+/// along with their address, in ROM order.
+pub(crate) fn make_program_with_instrs(
+    mut instrs: Vec<ParsedLine>,
+    options: &ProgramOptions,
+) -> Result<(Rom, Program), CompleteError> {
+    instrs = expand_pseudo_instructions(instrs, options)?;
+    let globals = extract_globals(&mut instrs);
+    let (mut ram, bss) = extract_ram(&mut instrs)?;
+    allocate_literals(&mut instrs, &mut ram);
+    // the zeroed data comes after the initialized one
+    ram.extend(bss);
+
+    collapse_long(&mut instrs);
+
+    let addresses = rom_addresses(&instrs, options.base)?;
+    let (rom_labels, ram_labels) = calculate_labels(&instrs, &addresses, &ram)?;
+    let (rom, ram) = process_lines(&instrs, &addresses, &ram, &rom_labels, &ram_labels)?;
+
+    let entry_points = globals
+        .into_iter()
+        // global data is not an entry point, and undefined globals are only warned about
+        .filter_map(|label| rom_labels.get(&label).map(|&addr| (label, addr)))
+        .collect();
+
+    let fill = BitVec::from_slice(&options.fill.to_be_bytes());
+    let bits = rom.iter().fold(BitVec::new(), |mut acc, (address, instr)| {
+        while acc.len() < (address - options.base) * 16 {
+            acc.extend_from_bitslice(&fill);
+        }
         acc.extend(instr.to_binary());
         acc
     });
 
-    let ram = ram.into_iter().fold(BitVec::new(), |mut acc, string| {
-        acc.extend(string.to_binary());
+    let ram = ram.into_iter().fold(BitVec::new(), |mut acc, bytes| {
+        acc.extend(bytes.to_binary());
         acc
     });
 
-    Ok(Program { instrs: rom, ram })
+    Ok((
+        rom,
+        Program {
+            instrs: bits,
+            ram,
+            rom_labels,
+            ram_labels,
+            entry_points,
+        },
+    ))
 }
 
 #[cfg(test)]
@@ -224,9 +546,10 @@ mod tests {
             }),
         ];
 
-        let ram = extract_ram(&mut instrs);
+        let (ram, _) = extract_ram(&mut instrs).unwrap();
 
-        let (rom_labels, ram_labels) = calculate_labels(&instrs, &ram);
+        let (rom_labels, ram_labels) =
+            calculate_labels(&instrs, &rom_addresses(&instrs, 0).unwrap(), &ram).unwrap();
         let expected_labels: LabelLookup = vec![("label1".to_owned(), 0), ("label2".to_owned(), 3)]
             .into_iter()
             .collect();
@@ -245,6 +568,7 @@ mod tests {
 
         assert_eq!(program.instrs, expected_rom);
         assert!(program.ram.is_empty());
+        assert_eq!(program.rom_labels, expected_labels);
     }
 
     #[test]
@@ -283,12 +607,217 @@ mod tests {
             0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 1, 1, 1,
         ];
 
+        assert_eq!(program.instrs, expected_rom);
+        assert_eq!(program.ram, expected_ram);
+    }
+
+    #[test]
+    fn string_after_comment() {
+        let mut instrs = crate::parse_lines(
+            "
+            label:
+            @ a comment
+            .p2align 2
+            .asciz \"Hi\"
+            .asciz \"!\"
+            ",
+        )
+        .unwrap();
+
+        let (ram, bss) = extract_ram(&mut instrs).unwrap();
+
+        assert!(instrs.is_empty());
         assert_eq!(
+            ram,
+            vec![
+                ParsedLine::Label("label".to_owned()),
+                ParsedLine::String("Hi".to_owned()),
+                ParsedLine::String("!".to_owned()),
+            ]
+        );
+        assert!(bss.is_empty());
+    }
+
+    #[test]
+    fn orphan_string() {
+        let instrs = vec![
+            ParsedLine::Label("label".to_owned()),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Lsrs,
+                args: Args::RdRmImm5(R0, R1, Immediate5::new(5).unwrap()),
+            }),
+            ParsedLine::String("Hello".to_owned()),
+        ];
+
+        let program = make_program(instrs);
+
+        assert!(matches!(
             program,
-            Program {
-                instrs: expected_rom,
-                ram: expected_ram
+            Err(CompleteError::StringWithoutLabel(string)) if string == "Hello"
+        ));
+    }
+
+    #[test]
+    fn entry_points() {
+        let instrs = crate::parse_lines(
+            "
+            .globl run
+            .p2align 1
+            movs r0, #1
+            movs r1, #2
+            run:
+            b run
+            ",
+        )
+        .unwrap();
+
+        let program = make_program(instrs).unwrap();
+
+        assert_eq!(program.entry_points, vec![("run".to_owned(), 2)]);
+        assert_eq!(program.instrs.len(), 3 * 16);
+    }
+
+    #[test]
+    fn undefined_global() {
+        let instrs = crate::parse_lines(
+            ".globl main
+run:
+b run",
+        )
+        .unwrap();
+
+        let program = make_program(instrs).unwrap();
+
+        assert!(program.entry_points.is_empty());
+    }
+
+    #[test]
+    fn literal_pool() {
+        let instrs = crate::parse_lines(
+            "
+            ldr r0, =1000
+            ldr r1, =0x12345678
+            ",
+        )
+        .unwrap();
+
+        let program = make_program(instrs).unwrap();
+
+        assert_eq!(program.ram_labels.get("=pool0"), Some(&0));
+        assert_eq!(program.ram_labels.get("=pool4"), Some(&4));
+        assert_eq!(program.ram.len(), 8 * 16);
+        assert_eq!(program.instrs.len(), 4 * 16);
+    }
+
+    /// Lines that make no sense on their own or in the wrong place
+    fn malformed_lines() -> Vec<ParsedLine> {
+        vec![
+            ParsedLine::Label("a".to_owned()),
+            ParsedLine::String("s".to_owned()),
+            ParsedLine::Long("a".to_owned()),
+            ParsedLine::Long("missing".to_owned()),
+            ParsedLine::Section(Section::Data),
+            ParsedLine::Section(Section::Text),
+            ParsedLine::Global("a".to_owned()),
+            ParsedLine::Global("missing".to_owned()),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::B,
+                args: Args::Label("a".to_owned()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Ldr3,
+                args: Args::RtLabel(R0, "a".to_owned()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RtLabel(R0, "a".to_owned()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RtConstant(R0, 1),
+            }),
+            ParsedLine::None,
+        ]
+    }
+
+    #[test]
+    fn malformed_sequences_do_not_panic() {
+        let lines = malformed_lines();
+        for a in &lines {
+            for b in &lines {
+                for c in &lines {
+                    let _ = make_program(vec![a.clone(), b.clone(), c.clone()]);
+                }
             }
-        );
+        }
+    }
+
+    #[test]
+    fn malformed_sequences_are_errors() {
+        let unresolved = vec![ParsedLine::Instr(FullInstr {
+            instr: Instr::Movs,
+            args: Args::RtLabel(R0, "a".to_owned()),
+        })];
+        assert!(matches!(
+            make_program(unresolved),
+            Err(CompleteError::Unresolved(_))
+        ));
+
+        let orphan = vec![ParsedLine::String("s".to_owned())];
+        assert!(matches!(
+            make_program(orphan),
+            Err(CompleteError::StringWithoutLabel(_))
+        ));
+
+        let invalid_ram = [ParsedLine::None];
+        assert!(matches!(
+            calculate_labels(&[], &[], &invalid_ram),
+            Err(CompleteError::InvalidRamLine(_))
+        ));
+    }
+
+    #[test]
+    fn far_label() {
+        let mut instrs = vec![ParsedLine::Instr(FullInstr {
+            instr: Instr::B,
+            args: Args::Label("far".to_owned()),
+        })];
+        let nop = ParsedLine::Instr(FullInstr {
+            instr: Instr::Lsls,
+            args: Args::RdRmImm5(R0, R0, Immediate5::new(0).unwrap()),
+        });
+        instrs.extend(std::iter::repeat_n(nop, 40_000));
+        instrs.push(ParsedLine::Label("far".to_owned()));
+
+        assert!(matches!(
+            make_program(instrs),
+            Err(CompleteError::JumpTooFar { .. })
+        ));
+    }
+
+    #[test]
+    fn base_address() {
+        let input = "
+            .globl start
+            start:
+            movs r0, #1
+            .loop:
+            bne .loop
+            b start
+            ";
+        let instrs = crate::parse_lines(input).unwrap();
+
+        let at_zero = make_program(instrs.clone()).unwrap();
+        let options = ProgramOptions {
+            base: 16,
+            ..Default::default()
+        };
+        let at_16 = make_program_with(instrs, &options).unwrap();
+
+        // branches are relative, their encoding does not move with the code
+        assert_eq!(at_zero.instrs, at_16.instrs);
+        assert_eq!(at_zero.rom_labels.get(".loop"), Some(&1));
+        assert_eq!(at_16.rom_labels.get(".loop"), Some(&17));
+        assert_eq!(at_16.entry_points, vec![("start".to_owned(), 16)]);
     }
 }
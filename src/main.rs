@@ -1,9 +1,22 @@
-use clap::{Parser, Subcommand};
-use parm_assembler::{export_to_logisim, make_program, parse_lines, ExportError, LOGISIM_HEADER};
+use clap::{Parser, Subcommand, ValueEnum};
+use parm_assembler::{
+    assemble_program, assemble_with_warnings, expand_includes_with_locations, export_to_logisim,
+    make_program, parse_lines, parse_lines_with, parse_logisim, ram_strings, ConvertOptions,
+    Endianness, ExportError, ExportOptions, IncludeError, LintOptions, ParseOptions, ParsedLine,
+    Program, ProgramOptions, SourceLocation, Warning, LOGISIM_HEADER,
+};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the watch mode looks for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
 
 #[derive(Subcommand, Debug)]
 enum Command {
@@ -11,16 +24,102 @@ enum Command {
     Assemble {
         /// The input file or directory
         input: PathBuf,
+        /// Print statistics about each assembled file
+        #[arg(short, long)]
+        verbose: bool,
+        /// Treat `;` as a statement separator instead of a comment
+        #[arg(long)]
+        multi_statement: bool,
+        /// Hexadecimal word written in the gaps left by `.org`
+        #[arg(long, value_parser = parse_hex_word, default_value = "0000")]
+        fill: u16,
+        /// Pad the ROM to this many words, which must be a power of two
+        #[arg(long, value_parser = parse_rom_size)]
+        rom_size: Option<usize>,
+        /// Assemble again whenever an input file changes
+        #[arg(long)]
+        watch: bool,
+        /// Fail if any warning is found
+        #[arg(long)]
+        werror: bool,
+        /// Warn when the program may run past its last instruction
+        #[arg(long)]
+        warn_fall_through: bool,
+        /// Write `address: value` rows, only setting the words the program uses
+        #[arg(long)]
+        addressed: bool,
+        /// The logisim conventions of the written memories
+        #[arg(long, value_enum, default_value_t = Target::ParmDefault)]
+        target: Target,
+        /// Print the parsed lines of each file before assembling it
+        #[arg(long)]
+        dump_ast: bool,
+    },
+    /// Assemble files without writing any output, failing if any of them is invalid
+    Check {
+        /// The input file or directory
+        input: PathBuf,
+        /// Fail if any warning is found
+        #[arg(long)]
+        werror: bool,
+    },
+    /// Compare the assembled ROM of a file with an expected logisim ROM
+    Diff {
+        /// The input file
+        input: PathBuf,
+        /// The expected ROM, in logisim format
+        expected: PathBuf,
     },
     /// Print a single instruction
     Print {
         /// The instruction
         instruction: String,
+        /// Pretty print the parsed lines
+        #[arg(long)]
+        dump_ast: bool,
+    },
+    /// Print the address of each label
+    Symbols {
+        /// The input file
+        input: PathBuf,
+    },
+    /// Print each string of the RAM along with its labels and address
+    Strings {
+        /// The input file
+        input: PathBuf,
     },
     /// Interactive mode
     Repl,
 }
 
+/// Named bundles of [`ConvertOptions`], for the logisim projects the memories are loaded into
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    /// Big endian raw images, as expected by the PARM circuit
+    ParmDefault,
+    /// Little endian raw images
+    #[value(name = "logisim-16le")]
+    Logisim16Le,
+    /// Big endian `address: value` rows
+    LogisimAddressed,
+}
+
+impl Target {
+    fn options(self) -> ConvertOptions {
+        match self {
+            Target::ParmDefault => ConvertOptions::default(),
+            Target::Logisim16Le => ConvertOptions {
+                endianness: Endianness::Little,
+                ..Default::default()
+            },
+            Target::LogisimAddressed => ConvertOptions {
+                addressed: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -28,6 +127,20 @@ struct Args {
     command: Command,
 }
 
+fn parse_hex_word(text: &str) -> Result<u16, std::num::ParseIntError> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    u16::from_str_radix(digits, 16)
+}
+
+fn parse_rom_size(text: &str) -> Result<usize, String> {
+    let size: usize = text.parse().map_err(|e| format!("{e}"))?;
+    if size.is_power_of_two() {
+        Ok(size)
+    } else {
+        Err(format!("{size} is not a power of two"))
+    }
+}
+
 fn list_files(path: PathBuf) -> Vec<PathBuf> {
     if let Ok(entries) = fs::read_dir(&path) {
         entries
@@ -42,63 +155,411 @@ fn list_files(path: PathBuf) -> Vec<PathBuf> {
     .collect()
 }
 
-fn read_file(path: &Path) -> String {
-    let mut file = File::open(path).unwrap();
+fn read_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
     let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// A source file, with the files it includes inlined
+struct Source {
+    text: String,
+    /// The file and line each line of the text comes from
+    locations: Vec<SourceLocation>,
+}
+
+/// Reads a source file, inlining the files it includes
+fn read_source(path: &Path) -> Result<Source, IncludeError> {
+    let text = read_file(path).map_err(|e| IncludeError::Unreadable {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    expand_includes_with_locations(&text, path, |file| fs::read_to_string(file))
+        .map(|(text, locations)| Source { text, locations })
+}
+
+#[derive(Debug)]
+struct Stats {
+    instructions: usize,
+    rom_words: usize,
+    ram_bytes: usize,
+    labels: usize,
+    warnings: Vec<Warning>,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  Instructions: {}", self.instructions)?;
+        writeln!(f, "  ROM words: {}", self.rom_words)?;
+        writeln!(f, "  RAM bytes: {}", self.ram_bytes)?;
+        write!(f, "  Labels: {}", self.labels)
+    }
+}
+
+/// Labels in `nm` style: address, region and name, sorted by address within each region.
+/// ROM addresses are word indices, RAM addresses are byte offsets.
+struct SymbolTable<'a>(&'a Program);
+
+impl Display for SymbolTable<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (region, labels) in [("rom", &self.0.rom_labels), ("ram", &self.0.ram_labels)] {
+            let mut labels: Vec<_> = labels.iter().collect();
+            labels.sort_by_key(|&(name, &address)| (address, name));
+            for (name, address) in labels {
+                writeln!(f, "{address:08x} {region} {name}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the symbols could be listed
+fn symbols(input: &Path) -> bool {
+    let source = read_source(input);
+    let program = match source
+        .as_ref()
+        .map_err(|e| ExportError::from(e.clone()))
+        .and_then(|contents| parse_lines(&contents.text).map_err(ExportError::from))
+        .and_then(|parsed| make_program(parsed).map_err(ExportError::from))
+    {
+        Ok(program) => program,
+        Err(e) => {
+            print_error(input, source.ok().as_ref(), e);
+            return false;
+        }
+    };
+    print!("{}", SymbolTable(&program));
+    true
+}
+
+/// Strings in the same style as [`SymbolTable`]: address, labels and text
+/// Returns whether the strings could be listed
+fn strings(input: &Path) -> bool {
+    let source = read_source(input);
+    let strings = match source
+        .as_ref()
+        .map_err(|e| ExportError::from(e.clone()))
+        .and_then(|contents| parse_lines(&contents.text).map_err(ExportError::from))
+        .and_then(|parsed| ram_strings(parsed).map_err(ExportError::from))
+    {
+        Ok(strings) => strings,
+        Err(e) => {
+            print_error(input, source.ok().as_ref(), e);
+            return false;
+        }
+    };
+    for string in strings {
+        let labels = match string.labels.join(",") {
+            labels if labels.is_empty() => "-".to_owned(),
+            labels => labels,
+        };
+        println!("{:08x} {labels} {:?}", string.offset, string.text);
+    }
+    true
+}
+
+/// What is printed when assembling, besides errors and warnings
+#[derive(Debug, Clone, Copy)]
+struct Verbosity {
+    /// Print statistics about each file
+    stats: bool,
+    /// Print the parsed lines of each file
+    dump_ast: bool,
+}
+
+/// Why a file could not be assembled
+#[derive(Debug)]
+enum FileError {
+    Export(ExportError),
+    /// The output could not be written
+    Write(io::Error),
+}
+
+impl From<ExportError> for FileError {
+    fn from(error: ExportError) -> Self {
+        FileError::Export(error)
+    }
+}
+
+impl From<IncludeError> for FileError {
+    fn from(error: IncludeError) -> Self {
+        FileError::Export(error.into())
+    }
+}
+
+fn process_file(
+    path: &Path,
+    options: &ExportOptions,
+    verbosity: Verbosity,
+) -> Result<Stats, FileError> {
+    let contents = read_source(path)?.text;
+
+    if verbosity.dump_ast {
+        let parsed = parse_lines_with(&contents, options.parse).map_err(ExportError::from)?;
+        println!("{parsed:#?}");
+    }
+
+    let assembly = assemble_program(&contents, options)?;
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    assembly
+        .output
+        .write_to_dir(dir, &stem)
+        .map_err(FileError::Write)?;
+
+    let program = &assembly.program;
+    Ok(Stats {
+        instructions: assembly.instructions,
+        rom_words: program.instrs.len() / 16,
+        // each byte of the RAM is stored in a 16 bits word
+        ram_bytes: program.ram.len() / 16,
+        labels: program.rom_labels.len() + program.ram_labels.len(),
+        warnings: assembly.warnings,
+    })
 }
 
-fn write_file(path: &Path, contents: &str) {
-    let mut file = File::create(path).unwrap();
-    file.write_all(contents.as_bytes()).unwrap();
+/// ANSI colors of the diagnostics, only used when stderr is a terminal and `NO_COLOR` is unset
+struct Style {
+    enabled: bool,
 }
 
-fn process_file(path: &Path) -> Result<(), ExportError> {
-    let contents = read_file(&path);
+impl Style {
+    const RED: &'static str = "\x1b[1;31m";
+    const YELLOW: &'static str = "\x1b[1;33m";
+    const BLUE: &'static str = "\x1b[1;34m";
+
+    fn detect() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self {
+            enabled: std::io::stderr().is_terminal() && !no_color,
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.enabled {
+            format!("{color}{text}\x1b[0m")
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+fn print_warnings(path: &Path, warnings: &[Warning]) {
+    let style = Style::detect();
+    for warning in warnings {
+        eprintln!(
+            "{}: {} {}",
+            path.display(),
+            style.paint(Style::YELLOW, "warning:"),
+            warning
+        );
+    }
+}
 
-    let output = export_to_logisim(&contents)?;
+/// Prints the error, followed by the offending line when it is known.
+/// The line is shown from the file it comes from, which may be included by `path`.
+fn print_error(path: &Path, source: Option<&Source>, error: ExportError) {
+    let (location, error) = match source {
+        Some(source) => error.locate(&source.locations),
+        None => (None, error),
+    };
+    let style = Style::detect();
+    eprintln!(
+        "{} {}: {}",
+        style.paint(Style::RED, "error:"),
+        location.as_ref().map_or(path, |l| &l.path).display(),
+        error
+    );
 
-    write_file(&path.with_extension("rom.bin"), &output.rom);
-    write_file(&path.with_extension("ram.bin"), &output.ram);
+    let Some((number, text)) = location.and_then(|location| {
+        let contents = fs::read_to_string(&location.path).ok()?;
+        let text = contents
+            .lines()
+            .nth(location.line.checked_sub(1)?)?
+            .to_owned();
+        Some((location.line, text))
+    }) else {
+        return;
+    };
+    let number = number.to_string();
+    let margin = " ".repeat(number.len());
+    let bar = style.paint(Style::BLUE, "|");
+    let indent = text.len() - text.trim_start().len();
+    let caret = "^".repeat(text.trim().chars().count());
 
-    Ok(())
+    eprintln!("{margin} {bar}");
+    eprintln!("{} {bar} {text}", style.paint(Style::BLUE, &number));
+    eprintln!(
+        "{margin} {bar} {}{}",
+        &text[..indent],
+        style.paint(Style::RED, &caret)
+    );
 }
 
-fn assemble(input: PathBuf) {
+/// Returns whether every file assembled successfully, without warnings if `werror` is set
+fn assemble(input: PathBuf, verbosity: Verbosity, werror: bool, options: ExportOptions) -> bool {
     let (succeeded, failed): (Vec<_>, Vec<_>) = list_files(input)
         .into_iter()
-        .map(|path| (process_file(path.as_ref()), path))
+        .map(|path| (process_file(path.as_ref(), &options, verbosity), path))
         .partition(|(result, _)| result.is_ok());
 
+    let mut success = failed.is_empty();
     for (result, path) in failed {
-        println!(
-            "Failed to process {}: {}",
-            path.display(),
-            result.unwrap_err()
-        );
+        match result.unwrap_err() {
+            FileError::Export(error) => {
+                let source = read_source(&path).ok();
+                print_error(&path, source.as_ref(), error);
+            }
+            FileError::Write(error) => eprintln!(
+                "{} {}: could not write the output: {error}",
+                Style::detect().paint(Style::RED, "error:"),
+                path.display()
+            ),
+        }
     }
-    for (_, path) in succeeded {
+    for (result, path) in succeeded {
+        let stats = result.unwrap();
+        print_warnings(&path, &stats.warnings);
+        success &= !werror || stats.warnings.is_empty();
         println!("Processed {}", path.display());
+        if verbosity.stats {
+            println!("{}", stats);
+        }
+    }
+    success
+}
+
+/// The modification time of the input files and of the files they include
+fn modification_times(input: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut files = BTreeSet::new();
+    for path in list_files(input.to_path_buf()) {
+        if let Ok(source) = read_source(&path) {
+            files.extend(source.locations.into_iter().map(|l| l.path));
+        }
+        files.insert(path);
+    }
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Assembles the input, then again each time a file is added, removed or modified,
+/// the included files being watched too
+fn watch(input: PathBuf, verbosity: Verbosity, werror: bool, options: ExportOptions) -> ! {
+    let mut times = modification_times(&input);
+    assemble(input.clone(), verbosity, werror, options.clone());
+    println!("Watching {} for changes", input.display());
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        let current = modification_times(&input);
+        if current != times {
+            times = current;
+            assemble(input.clone(), verbosity, werror, options.clone());
+        }
+    }
+}
+
+/// Returns whether every file assembled successfully, without warnings if `werror` is set
+fn check(input: PathBuf, werror: bool) -> bool {
+    let mut success = true;
+    for path in list_files(input) {
+        let source = read_source(&path);
+        match source
+            .as_ref()
+            .map_err(|e| ExportError::from(e.clone()))
+            .and_then(|source| assemble_with_warnings(&source.text))
+        {
+            Ok((_, warnings)) => {
+                print_warnings(&path, &warnings);
+                success &= !werror || warnings.is_empty();
+                println!("{}: ok", path.display());
+            }
+            Err(e) => {
+                print_error(&path, source.ok().as_ref(), e);
+                success = false;
+            }
+        }
+    }
+    success
+}
+
+/// Prints each word that differs, returns whether the ROMs are identical
+fn diff(input: &Path, expected: &Path) -> bool {
+    let actual = read_source(input)
+        .map_err(ExportError::from)
+        .and_then(|source| export_to_logisim(&source.text))
+        .map_err(|e| e.to_string())
+        .and_then(|program| parse_logisim(&program.rom).map_err(|e| e.to_string()));
+    let actual = match actual {
+        Ok(actual) => actual,
+        Err(e) => {
+            eprintln!("Failed to process {}: {}", input.display(), e);
+            return false;
+        }
+    };
+    let expected = match fs::read_to_string(expected)
+        .map_err(|e| e.to_string())
+        .and_then(|image| parse_logisim(&image).map_err(|e| e.to_string()))
+    {
+        Ok(expected) => expected,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", expected.display(), e);
+            return false;
+        }
+    };
+
+    let word = |words: &[u16], address: usize| {
+        words
+            .get(address)
+            .map_or("----".to_owned(), |word| format!("{word:04x}"))
+    };
+
+    let mut identical = true;
+    for address in 0..actual.len().max(expected.len()) {
+        if actual.get(address) != expected.get(address) {
+            println!(
+                "{address:04x}: expected {}, got {}",
+                word(&expected, address),
+                word(&actual, address)
+            );
+            identical = false;
+        }
     }
+    identical
 }
 
-fn print(instr: &str) {
+/// Returns whether the instruction could be assembled
+fn print(instr: &str, dump_ast: bool) -> bool {
     let parsed = match parse_lines(instr) {
         Ok(parsed) => parsed,
         Err(e) => {
-            println!("Failed to parse: {}", e);
-            return;
+            eprintln!("Failed to parse: {}", e);
+            return false;
         }
     };
 
-    println!("Parsed lines: {:?}", parsed);
+    if dump_ast {
+        println!("Parsed lines: {parsed:#?}");
+    } else {
+        println!("Parsed lines: {:?}", parsed);
+    }
+    for line in &parsed {
+        if let ParsedLine::Instr(instr) = line {
+            println!("Instruction: {instr}");
+        }
+    }
 
     let program = match make_program(parsed.clone()) {
         Ok(program) => program,
         Err(e) => {
-            println!("Failed to make program: {}", e);
-            return;
+            eprintln!("Failed to make program: {}", e);
+            return false;
         }
     };
 
@@ -108,13 +569,14 @@ fn print(instr: &str) {
     let logisim = match export_to_logisim(instr) {
         Ok(logisim) => logisim,
         Err(e) => {
-            println!("Failed to export to logisim: {}", e);
-            return;
+            eprintln!("Failed to export to logisim: {}", e);
+            return false;
         }
     };
 
     let logisim_rom = logisim.rom.replace(LOGISIM_HEADER, "");
     println!("Logisim ROM: {logisim_rom}");
+    true
 }
 
 fn repl() {
@@ -129,16 +591,87 @@ fn repl() {
         if input == "exit" {
             break;
         }
-        print(input);
+        // errors are printed, the session goes on
+        print(input, false);
     }
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
 
     match args.command {
-        Command::Assemble { input } => assemble(input),
-        Command::Print { instruction } => print(&instruction),
+        Command::Assemble {
+            input,
+            verbose,
+            multi_statement,
+            fill,
+            rom_size,
+            watch: watching,
+            werror,
+            warn_fall_through,
+            addressed,
+            target,
+            dump_ast,
+        } => {
+            let verbosity = Verbosity {
+                stats: verbose,
+                dump_ast,
+            };
+            let mut convert = target.options();
+            convert.addressed |= addressed;
+            let options = ExportOptions {
+                parse: ParseOptions {
+                    multi_statement,
+                    ..Default::default()
+                },
+                program: ProgramOptions {
+                    fill,
+                    ..Default::default()
+                },
+                rom_size,
+                lint: LintOptions {
+                    fall_through: warn_fall_through,
+                },
+                rom: convert.clone(),
+                ram: convert,
+                ..Default::default()
+            };
+            if watching {
+                watch(input, verbosity, werror, options);
+            }
+            if !assemble(input, verbosity, werror, options) {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Check { input, werror } => {
+            if !check(input, werror) {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Diff { input, expected } => {
+            if !diff(&input, &expected) {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Print {
+            instruction,
+            dump_ast,
+        } => {
+            if !print(&instruction, dump_ast) {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Symbols { input } => {
+            if !symbols(&input) {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Strings { input } => {
+            if !strings(&input) {
+                return ExitCode::FAILURE;
+            }
+        }
         Command::Repl => repl(),
     }
+    ExitCode::SUCCESS
 }
@@ -1,7 +1,9 @@
-use nom::bytes::complete::{tag_no_case, take_till, take_while};
-use nom::character::complete::{char, line_ending, multispace1, space0, space1};
-use nom::combinator::{eof, map_opt, map_res, value};
-use nom::error::{convert_error, ErrorKind, VerboseError};
+use nom::bytes::complete::{tag, tag_no_case, take_till, take_while, take_while1, take_while_m_n};
+use nom::character::complete::{
+    char, hex_digit1, line_ending, multispace1, one_of, satisfy, space0, space1,
+};
+use nom::combinator::{consumed, eof, map_opt, map_res, not, recognize, success, value, verify};
+use nom::error::{convert_error, ErrorKind, VerboseError, VerboseErrorKind};
 use nom::multi::many_till;
 use nom::sequence::{delimited, pair, preceded, terminated};
 use nom::{
@@ -9,27 +11,41 @@ use nom::{
     character::complete::digit1,
     combinator::{map, opt},
     sequence::tuple,
-    Finish, IResult,
+    Finish, IResult, Offset,
 };
 use regex::Regex;
 use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
-use crate::instructions::{Args, FullInstr, Immediate, Immediate8, Instr, Reg};
+use crate::instructions::{
+    Args, FullInstr, Immediate, Immediate11, Immediate5, Immediate8, Immediate8S, Immediate8W,
+    ImmediateError, Instr, Reg, RegError, SignedImmediate,
+};
+use crate::preprocess::{self, PreprocessError};
 use crate::utils::{unescape_string, Appliable};
 
 pub type Err<'a> = VerboseError<&'a str>;
 
 trait Parseable: Sized {
-    fn parse(input: &str) -> IResult<&str, Self, Err>;
+    fn parse(input: &str) -> IResult<&str, Self, Err<'_>>;
 }
 
+/// Context of the failure raised on registers we cannot use, such as `r8`
+const INVALID_REGISTER: &str = "invalid register";
+
 impl Parseable for Reg {
-    fn parse(input: &str) -> IResult<&str, Reg, Err> {
-        let standard_reg = map_res(
-            preceded(tag_no_case("r"), map_res(digit1, str::parse::<u8>)),
-            Reg::try_from,
-        );
+    fn parse(input: &str) -> IResult<&str, Reg, Err<'_>> {
+        let standard_reg = |input| {
+            let (rest, number) =
+                preceded(tag_no_case("r"), map_res(digit1, str::parse::<u8>))(input)?;
+            match Reg::try_from(number) {
+                Ok(reg) => Ok((rest, reg)),
+                // no other operand can start like a register, so there is no point in backtracking
+                Err(_) => Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(input, VerboseErrorKind::Context(INVALID_REGISTER))],
+                })),
+            }
+        };
 
         let sp = value(Reg::SP, tag_no_case("sp"));
         let pc = value(Reg::PC, tag_no_case("pc"));
@@ -38,62 +54,260 @@ impl Parseable for Reg {
     }
 }
 
+/// Context of the failure raised on registers that do not fit in a 3 bits field, such as `sp`
+const HIGH_REGISTER: &str = "high register";
+
+/// A register encoded in 3 bits. `sp` and `pc` only have dedicated encodings, like `[sp, #imm]`.
+fn parse_low_reg(input: &str) -> IResult<&str, Reg, Err<'_>> {
+    let (rest, reg) = Reg::parse(input)?;
+    if reg.is_low() {
+        Ok((rest, reg))
+    } else {
+        Err(nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(HIGH_REGISTER))],
+        }))
+    }
+}
+
+/// `Qn(x)`: the fixed-point representation of `x` with `n` fractional bits, rounded
+fn parse_fixed_point(input: &str) -> IResult<&str, u16, Err<'_>> {
+    let number = recognize(pair(digit1, opt(pair(char('.'), digit1))));
+    map_opt(
+        pair(
+            preceded(tag_no_case("q"), map_res(digit1, str::parse::<u8>)),
+            delimited(char('('), map_res(number, str::parse::<f64>), char(')')),
+        ),
+        |(bits, value)| {
+            let fixed = (value * 2f64.powi(bits.into())).round();
+            (fixed <= u16::MAX.into()).then_some(fixed as u16)
+        },
+    )(input)
+}
+
+/// Context of the failure raised on character literals outside of ASCII, such as `'é'`
+const NON_ASCII_CHAR: &str = "non-ASCII character";
+
+/// `'c'`: the code of a single ASCII character, escapes included.
+/// Other characters take several bytes in `.asciz` strings, so they have no single code.
+fn parse_char_literal(input: &str) -> IResult<&str, u16, Err<'_>> {
+    let escape = recognize(pair(
+        char('\\'),
+        alt((
+            recognize(pair(char('u'), hex_digit1)),
+            recognize(satisfy(|_| true)),
+        )),
+    ));
+    let plain = recognize(satisfy(|c| c != '\'' && c != '\\'));
+    let (rest, c) = map_opt(
+        delimited(char('\''), alt((escape, plain)), char('\'')),
+        |text: &str| {
+            let text = unescape_string(text);
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        },
+    )(input)?;
+
+    if c.is_ascii() {
+        Ok((rest, c as u16))
+    } else {
+        Err(nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(NON_ASCII_CHAR))],
+        }))
+    }
+}
+
+type Operation = fn(i64, i64) -> Option<i64>;
+
+/// Parses operands separated by the given operators, evaluating them from left to right.
+fn parse_operations<'a>(
+    input: &'a str,
+    operators: &[(&'static str, Operation)],
+    operand: fn(&'a str) -> IResult<&'a str, i64, Err<'a>>,
+) -> IResult<&'a str, i64, Err<'a>> {
+    let (mut input, mut value) = operand(input)?;
+    loop {
+        let next = operators.iter().find_map(|(symbol, operation)| {
+            preceded(space0::<_, Err>, tag(*symbol))(input)
+                .ok()
+                .map(|(rest, _)| (rest, operation))
+        });
+        let Some((rest, operation)) = next else {
+            return Ok((input, value));
+        };
+        let (rest, rhs) = preceded(space0, operand)(rest)?;
+        value = operation(value, rhs).ok_or(nom::Err::Error(
+            nom::error::ParseError::from_error_kind(input, ErrorKind::Verify),
+        ))?;
+        input = rest;
+    }
+}
+
+fn parse_shift(input: &str) -> IResult<&str, i64, Err<'_>> {
+    let shl: Operation = |a, b| a.checked_shl(b.try_into().ok()?);
+    let shr: Operation = |a, b| a.checked_shr(b.try_into().ok()?);
+    parse_operations(input, &[("<<", shl), (">>", shr)], parse_sum)
+}
+
+fn parse_sum(input: &str) -> IResult<&str, i64, Err<'_>> {
+    parse_operations(
+        input,
+        &[("+", i64::checked_add), ("-", i64::checked_sub)],
+        parse_product,
+    )
+}
+
+fn parse_product(input: &str) -> IResult<&str, i64, Err<'_>> {
+    parse_operations(
+        input,
+        &[("*", i64::checked_mul), ("/", i64::checked_div)],
+        parse_factor,
+    )
+}
+
+fn parse_factor(input: &str) -> IResult<&str, i64, Err<'_>> {
+    alt((
+        map(parse_number, i64::from),
+        map(parse_char_literal, i64::from),
+        parse_parenthesized,
+        map(preceded(char('-'), parse_factor), |value| -value),
+    ))(input)
+}
+
+/// `(expression)`, supporting `+ - * / << >>` with the usual precedence.
+/// The `.equ` symbols have already been replaced by their value, labels are not supported.
+fn parse_parenthesized(input: &str) -> IResult<&str, i64, Err<'_>> {
+    delimited(
+        pair(char('('), space0),
+        parse_shift,
+        pair(space0, char(')')),
+    )(input)
+}
+
+/// Context of the failure raised on x86 style immediates, such as `$5`
+const DOLLAR_IMMEDIATE: &str = "dollar immediate";
+
+/// Context of the failure raised on negative values given to an unsigned field, such as `#-1`
+const NEGATIVE_IMMEDIATE: &str = "negative immediate";
+
+/// Context of the failure raised on word offsets that are not a multiple of 4, such as `[sp, #6]`
+const UNALIGNED_OFFSET: &str = "unaligned offset";
+
+/// The value of an unsigned immediate such as `#4`, before its range is checked
+fn parse_unsigned_value(input: &str) -> IResult<&str, u16, Err<'_>> {
+    preceded(
+        char('#'),
+        alt((
+            parse_fixed_point,
+            parse_char_literal,
+            map_opt(parse_parenthesized, |value| u16::try_from(value).ok()),
+            map_opt(parse_number, |value| u16::try_from(value).ok()),
+        )),
+    )(input)
+}
+
 impl<const N: u8, const WIDE: bool> Parseable for Immediate<N, WIDE> {
-    fn parse(input: &str) -> IResult<&str, Immediate<N, WIDE>, Err> {
+    fn parse(input: &str) -> IResult<&str, Immediate<N, WIDE>, Err<'_>> {
+        if input.starts_with('$') {
+            return Err(nom::Err::Failure(VerboseError {
+                errors: vec![(input, VerboseErrorKind::Context(DOLLAR_IMMEDIATE))],
+            }));
+        }
+        if let Ok((_, value)) = preceded(tag("#-"), parse_number)(input) {
+            if value > 0 {
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(input, VerboseErrorKind::Context(NEGATIVE_IMMEDIATE))],
+                }));
+            }
+        }
+        let (rest, value) = parse_unsigned_value(input)?;
+        match Immediate::<N, WIDE>::new(value) {
+            Ok(imm) => Ok((rest, imm)),
+            // a misaligned offset fits no other form either
+            Err(ImmediateError::UnalignedOffset { .. }) => Err(nom::Err::Failure(VerboseError {
+                errors: vec![(input, VerboseErrorKind::Context(UNALIGNED_OFFSET))],
+            })),
+            Err(_) => Err(nom::Err::Error(nom::error::ParseError::from_error_kind(
+                input,
+                ErrorKind::MapRes,
+            ))),
+        }
+    }
+}
+
+impl<const N: u8, const WIDE: bool> Parseable for SignedImmediate<N, WIDE> {
+    fn parse(input: &str) -> IResult<&str, SignedImmediate<N, WIDE>, Err<'_>> {
         map_res(
             preceded(
                 char('#'),
-                map_res(take_while(|c: char| c.is_numeric()), str::parse::<u16>),
+                map_opt(pair(opt(char('-')), parse_number), |(minus, value)| {
+                    let value = i64::from(value);
+                    i16::try_from(if minus.is_some() { -value } else { value }).ok()
+                }),
             ),
-            Immediate::<N, WIDE>::new,
+            SignedImmediate::<N, WIDE>::new,
         )(input)
     }
 }
 
-fn parse_rd_rm_imm5(input: &str) -> IResult<&str, Args, Err> {
+fn parse_rd_rm_imm5(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
             preceded(parse_separator, Immediate::parse),
         )),
         Args::RdRmImm5.make_appliable(),
     )(input)
 }
 
-fn parse_rd_rn_rm(input: &str) -> IResult<&str, Args, Err> {
+fn parse_rd_rn_rm(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
         )),
         Args::RdRnRm.make_appliable(),
     )(input)
 }
 
-fn parse_rd_rn_imm3(input: &str) -> IResult<&str, Args, Err> {
-    map(
-        tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Immediate::parse),
-        )),
-        Args::RdRnImm3.make_appliable(),
-    )(input)
+/// `rd, rn, #imm`, the immediate being parsed with the 8 bits range of the other forms
+fn parse_rd_rn_imm8_operands(input: &str) -> IResult<&str, (Reg, Reg, Immediate8), Err<'_>> {
+    tuple((
+        preceded(parse_separator, parse_low_reg),
+        preceded(parse_separator, parse_low_reg),
+        preceded(parse_separator, Immediate8::parse),
+    ))(input)
+}
+
+/// Context of the failure raised on `adds rd, rn, #imm` with an immediate that only fits in 8 bits
+const IMM3_TOO_LARGE: &str = "3 bits immediate too large";
+
+fn parse_rd_rn_imm3(input: &str) -> IResult<&str, Args, Err<'_>> {
+    let (rest, (rd, rn, imm)) = parse_rd_rn_imm8_operands(input)?;
+    match Immediate::new(imm.0) {
+        Ok(imm) => Ok((rest, Args::RdRnImm3(rd, rn, imm))),
+        // the 8 bits form only has a single register, it must not be picked silently
+        Err(_) => Err(nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(IMM3_TOO_LARGE))],
+        })),
+    }
 }
 
-fn parse_rd_imm8(input: &str) -> IResult<&str, Args, Err> {
+fn parse_rd_imm8(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         tuple((
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
             preceded(parse_separator, Immediate::parse),
         )),
         Args::RdImm8.make_appliable(),
     )(input)
 }
 
-fn parse_sp_imm7(input: &str) -> IResult<&str, Args, Err> {
+fn parse_sp_imm7(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         preceded(
             tuple((parse_separator, tag_no_case("sp"), parse_separator)),
@@ -103,38 +317,33 @@ fn parse_sp_imm7(input: &str) -> IResult<&str, Args, Err> {
     )(input)
 }
 
-fn parse_two_regs(input: &str) -> IResult<&str, Args, Err> {
+fn parse_two_regs(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
         )),
         Args::TwoRegs.make_appliable(),
     )(input)
 }
 
-fn parse_rdm_rn_rdm(input: &str) -> IResult<&str, Args, Err> {
-    map_opt(
+/// `muls rd, rn, rm`: the destination must be one of the sources, which is checked on completion
+fn parse_rdm_rn_rdm(input: &str) -> IResult<&str, Args, Err<'_>> {
+    map(
         tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
         )),
-        |(r1, r2, r1_)| {
-            if r1 == r1_ {
-                Some(Args::TwoRegs(r1, r2))
-            } else {
-                None
-            }
-        },
+        |(rd, rn, rm)| Args::RdRnRm(rd, rn, rm),
     )(input)
 }
 
-fn parse_rdrn_imm0(input: &str) -> IResult<&str, Args, Err> {
+fn parse_rdrn_imm0(input: &str) -> IResult<&str, Args, Err<'_>> {
     map_opt(
         tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
             preceded(parse_separator, Immediate8::parse),
         )),
         |(rd, rn, imm0)| {
@@ -147,37 +356,88 @@ fn parse_rdrn_imm0(input: &str) -> IResult<&str, Args, Err> {
     )(input)
 }
 
-fn parse_rt_sp_imm8(input: &str) -> IResult<&str, Args, Err> {
+/// `rsbs rd, rn` and `neg rd, rn`, the `#0` being implied
+fn parse_rdrn(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         tuple((
-            preceded(parse_separator, Reg::parse),
-            preceded(
-                parse_separator,
-                delimited(
-                    tag_no_case("[sp"),
-                    map(opt(preceded(parse_separator, Immediate::parse)), |i| {
-                        i.or_else(|| Some(Immediate::new(0).unwrap())).unwrap()
-                    }),
-                    char(']'),
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_low_reg),
+        )),
+        Args::RdRnImm0.make_appliable(),
+    )(input)
+}
+
+/// `[`, with optional whitespace after it
+fn parse_open_bracket(input: &str) -> IResult<&str, char, Err<'_>> {
+    terminated(char('['), space0)(input)
+}
+
+/// `]`, with optional whitespace before it
+fn parse_close_bracket(input: &str) -> IResult<&str, char, Err<'_>> {
+    preceded(space0, char(']'))(input)
+}
+
+/// Same as [`parse_separator`], but also allows whitespace before the comma
+fn parse_inner_separator(input: &str) -> IResult<&str, &str, Err<'_>> {
+    preceded(space0, parse_separator)(input)
+}
+
+/// `rt, [base, #imm]`, where `base` is `sp` or `pc`
+fn parse_rt_base_imm8<'a>(
+    base: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Reg, Immediate8W), Err<'a>> {
+    tuple((
+        preceded(parse_separator, parse_low_reg),
+        preceded(
+            parse_separator,
+            delimited(
+                pair(parse_open_bracket, tag_no_case(base)),
+                map(
+                    opt(preceded(parse_inner_separator, Immediate::parse)),
+                    |i| i.or_else(|| Some(Immediate::new(0).unwrap())).unwrap(),
                 ),
+                parse_close_bracket,
+            ),
+        ),
+    ))
+}
+
+fn parse_rt_sp_imm8(input: &str) -> IResult<&str, Args, Err<'_>> {
+    map(parse_rt_base_imm8("sp"), Args::RtSpImm8W.make_appliable())(input)
+}
+
+/// `rt, [pc, #imm]`: a load relative to the program counter
+fn parse_rt_pc_imm8(input: &str) -> IResult<&str, Args, Err<'_>> {
+    map(parse_rt_base_imm8("pc"), Args::RtPcImm8W.make_appliable())(input)
+}
+
+/// `rd, sp, #imm`: the address of a stack slot
+fn parse_rd_sp_imm8(input: &str) -> IResult<&str, Args, Err<'_>> {
+    map(
+        tuple((
+            preceded(parse_separator, parse_low_reg),
+            preceded(
+                tuple((parse_separator, tag_no_case("sp"), parse_separator)),
+                Immediate::parse,
             ),
         )),
-        Args::RtSpImm8W.make_appliable(),
+        Args::RdSpImm8W.make_appliable(),
     )(input)
 }
 
-fn parse_rt_rn_imm5(input: &str) -> IResult<&str, Args, Err> {
+fn parse_rt_rn_imm5(input: &str) -> IResult<&str, Args, Err<'_>> {
+    // [sp, #imm] has its own encoding
     let inner_braces = pair(
-        preceded(parse_separator, Reg::parse),
-        opt(preceded(parse_separator, Immediate::parse)),
+        verify(Reg::parse, Reg::is_low),
+        opt(preceded(parse_inner_separator, Immediate::parse)),
     );
 
     map(
         pair(
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
             preceded(
                 parse_separator,
-                delimited(char('['), inner_braces, char(']')),
+                delimited(parse_open_bracket, inner_braces, parse_close_bracket),
             ),
         ),
         |(rt, (rn, imm5))| {
@@ -186,51 +446,175 @@ fn parse_rt_rn_imm5(input: &str) -> IResult<&str, Args, Err> {
     )(input)
 }
 
-fn parse_label(input: &str) -> IResult<&str, &str, Err> {
-    take_till(|c: char| c.is_whitespace())(input)
+/// Same as [`parse_rt_rn_imm5`], but the offset is a multiple of 2 encoded in halfwords
+fn parse_rt_rn_imm5_halfword(input: &str) -> IResult<&str, Args, Err<'_>> {
+    let offset = map_opt(Immediate::<6, false>::parse, |imm| {
+        if imm.0 % 2 == 0 {
+            Immediate5::new(imm.0 / 2).ok().filter(|imm| imm.0 < 32)
+        } else {
+            None
+        }
+    });
+    let inner_braces = pair(
+        verify(Reg::parse, Reg::is_low),
+        opt(preceded(parse_inner_separator, offset)),
+    );
+
+    map(
+        pair(
+            preceded(parse_separator, parse_low_reg),
+            preceded(
+                parse_separator,
+                delimited(parse_open_bracket, inner_braces, parse_close_bracket),
+            ),
+        ),
+        |(rt, (rn, imm5))| {
+            Args::RtRnImm5H(rt, rn, imm5.unwrap_or_else(|| Immediate::new(0).unwrap()))
+        },
+    )(input)
 }
 
-fn parse_label_definition(input: &str) -> IResult<&str, &str, Err> {
-    terminated(
-        take_while(|c: char| c.is_alphanumeric() || c == '.' || c == '_' || c == '$'),
-        char(':'),
+/// Labels may contain dots and dollars anywhere, as in `.L.str.1` or `foo$bar`
+pub(crate) fn is_label_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '.' || c == '_' || c == '$'
+}
+
+fn parse_label(input: &str) -> IResult<&str, &str, Err<'_>> {
+    take_while1(is_label_char)(input)
+}
+
+fn parse_label_definition(input: &str) -> IResult<&str, &str, Err<'_>> {
+    terminated(take_while(is_label_char), char(':'))(input)
+}
+
+fn parse_no_args(input: &str) -> IResult<&str, Args, Err<'_>> {
+    success(Args::None)(input)
+}
+
+fn parse_imm8(input: &str) -> IResult<&str, Args, Err<'_>> {
+    map(
+        preceded(parse_separator, Immediate8::parse),
+        Args::Immediate8,
     )(input)
 }
 
-fn parse_label_args(input: &str) -> IResult<&str, Args, Err> {
+fn parse_label_args(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(preceded(parse_separator, parse_label), |label| {
         Args::Label(label.to_owned())
     })(input)
 }
 
-fn parse_rt_label(input: &str) -> IResult<&str, Args, Err> {
+/// A label, or an offset given directly in instructions
+fn parse_label_or_imm(input: &str) -> IResult<&str, Args, Err<'_>> {
+    alt((
+        parse_label_args,
+        map(
+            preceded(parse_separator, Immediate8S::parse),
+            Args::Immediate8S,
+        ),
+    ))(input)
+}
+
+/// Same as [`parse_label_or_imm`], for unconditional branches
+fn parse_label_or_imm11(input: &str) -> IResult<&str, Args, Err<'_>> {
+    alt((
+        parse_label_args,
+        map(
+            preceded(parse_separator, Immediate11::parse),
+            Args::Immediate11,
+        ),
+    ))(input)
+}
+
+fn parse_rt_label(input: &str) -> IResult<&str, Args, Err<'_>> {
     map(
         pair(
-            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, parse_low_reg),
             preceded(parse_separator, parse_label),
         ),
         |(reg, str)| Args::RtLabel(reg, str.to_owned()),
     )(input)
 }
 
-fn parse_separator(input: &str) -> IResult<&str, &str, Err> {
+/// Context of the failure raised on numbers that cannot be read unambiguously, such as `0x`,
+/// `0b2` or `08`
+const INVALID_NUMBER: &str = "invalid number";
+
+/// A decimal, `0x` prefixed hexadecimal or `0b` prefixed binary number.
+/// Decimal numbers cannot start with a zero, which other assemblers read as octal.
+fn parse_number(input: &str) -> IResult<&str, u32, Err<'_>> {
+    let invalid = || {
+        nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(INVALID_NUMBER))],
+        })
+    };
+    let too_large = || {
+        nom::Err::Error(nom::error::ParseError::from_error_kind(
+            input,
+            ErrorKind::MapRes,
+        ))
+    };
+
+    for (prefix, radix) in [("0x", 16), ("0b", 2)] {
+        if let Ok((rest, _)) = tag_no_case::<_, _, Err>(prefix)(input) {
+            // the digits go up to the next separator, so that `0x1g` is not read as `0x1`
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let (digits, rest) = rest.split_at(end);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+                return Err(invalid());
+            }
+            let value = u32::from_str_radix(digits, radix).map_err(|_| too_large())?;
+            return Ok((rest, value));
+        }
+    }
+
+    let (rest, digits) = digit1(input)?;
+    if digits.len() > 1 && digits.starts_with('0') {
+        return Err(invalid());
+    }
+    let value = digits.parse().map_err(|_| too_large())?;
+    Ok((rest, value))
+}
+
+/// Handles the `=<constant>` operand of `ldr rt, =<constant>`
+fn parse_constant(input: &str) -> IResult<&str, u32, Err<'_>> {
+    preceded(char('='), parse_number)(input)
+}
+
+fn parse_rt_constant(input: &str) -> IResult<&str, Args, Err<'_>> {
+    map(
+        pair(
+            preceded(parse_separator, parse_low_reg),
+            preceded(parse_separator, parse_constant),
+        ),
+        |(reg, constant)| Args::RtConstant(reg, constant),
+    )(input)
+}
+
+fn parse_separator(input: &str) -> IResult<&str, &str, Err<'_>> {
     preceded(opt(char(',')), space0)(input)
 }
 
-type ParseArgs = fn(&str) -> IResult<&str, Args, Err>;
+type ParseArgs = fn(&str) -> IResult<&str, Args, Err<'_>>;
 
 /// The full list of supported instructions.
-const INSTRUCTIONS: &[(Instr, ParseArgs); 50] = &[
+/// Its order only matters when two candidates consume the same input, see [`parse_instr_from`].
+const INSTRUCTIONS: &[(Instr, ParseArgs); 69] = &[
     (Instr::Lsls, parse_rd_rm_imm5),
     (Instr::Lsrs, parse_rd_rm_imm5),
     (Instr::Asrs, parse_rd_rm_imm5),
     (Instr::Adds, parse_rd_rn_rm),
+    // must come before `add rd, rn, #imm3`, which would take sp as a low register
+    (Instr::AddSpReg, parse_rd_sp_imm8),
     (Instr::Adds2, parse_rd_rn_imm3),
     (Instr::Adds3, parse_rd_imm8),
     (Instr::Subs, parse_rd_rn_rm),
     (Instr::Subs2, parse_rd_rn_imm3),
     (Instr::Subs3, parse_rd_imm8),
     (Instr::Movs, parse_rd_imm8),
+    (Instr::Movs2, parse_two_regs),
     (Instr::Rsbs, parse_rdrn_imm0),
     (Instr::Ands, parse_two_regs),
     (Instr::Eors, parse_two_regs),
@@ -241,83 +625,140 @@ const INSTRUCTIONS: &[(Instr, ParseArgs); 50] = &[
     (Instr::Sbcs, parse_two_regs),
     (Instr::Rors, parse_two_regs),
     (Instr::Tst, parse_two_regs),
-    (Instr::Rsbs, parse_two_regs),
+    (Instr::Rsbs, parse_rdrn),
     (Instr::Cmp, parse_two_regs),
     (Instr::Cmp2, parse_rd_imm8),
     (Instr::Cmn, parse_two_regs),
     (Instr::Orrs, parse_two_regs),
     (Instr::Muls, parse_rdm_rn_rdm),
+    // `muls rd, rm` is already `muls rd, rm, rd` in encoded form
+    (Instr::Muls, parse_two_regs),
     (Instr::Bics, parse_two_regs),
     (Instr::Mvns, parse_two_regs),
     (Instr::Str, parse_rt_sp_imm8),
     (Instr::Ldr, parse_rt_sp_imm8),
     (Instr::Ldr2, parse_rt_rn_imm5),
+    (Instr::Ldr3, parse_rt_constant),
     (Instr::Ldr3, parse_rt_label),
+    (Instr::LdrPc, parse_rt_pc_imm8),
+    (Instr::Strh, parse_rt_rn_imm5_halfword),
+    (Instr::Ldrh, parse_rt_rn_imm5_halfword),
     (Instr::AddSp, parse_sp_imm7),
     (Instr::SubSp, parse_sp_imm7),
-    (Instr::Beq, parse_label_args),
-    (Instr::Bne, parse_label_args),
-    (Instr::Bcs, parse_label_args),
-    (Instr::Bcc, parse_label_args),
-    (Instr::Bmi, parse_label_args),
-    (Instr::Bpl, parse_label_args),
-    (Instr::Bvs, parse_label_args),
-    (Instr::Bvc, parse_label_args),
-    (Instr::Bhi, parse_label_args),
-    (Instr::Bls, parse_label_args),
-    (Instr::Bge, parse_label_args),
-    (Instr::Blt, parse_label_args),
-    (Instr::Bgt, parse_label_args),
-    (Instr::Ble, parse_label_args),
-    (Instr::Bal, parse_label_args),
-    (Instr::B, parse_label_args),
+    (Instr::Rev, parse_two_regs),
+    (Instr::Rev16, parse_two_regs),
+    (Instr::Revsh, parse_two_regs),
+    (Instr::Sxth, parse_two_regs),
+    (Instr::Sxtb, parse_two_regs),
+    (Instr::Uxth, parse_two_regs),
+    (Instr::Uxtb, parse_two_regs),
+    (Instr::Wfi, parse_no_args),
+    (Instr::Wfe, parse_no_args),
+    (Instr::Sev, parse_no_args),
+    (Instr::Bkpt, parse_imm8),
+    (Instr::Svc, parse_imm8),
+    (Instr::Beq, parse_label_or_imm),
+    (Instr::Bne, parse_label_or_imm),
+    (Instr::Bcs, parse_label_or_imm),
+    (Instr::Bcc, parse_label_or_imm),
+    (Instr::Bmi, parse_label_or_imm),
+    (Instr::Bpl, parse_label_or_imm),
+    (Instr::Bvs, parse_label_or_imm),
+    (Instr::Bvc, parse_label_or_imm),
+    (Instr::Bhi, parse_label_or_imm),
+    (Instr::Bls, parse_label_or_imm),
+    (Instr::Bge, parse_label_or_imm),
+    (Instr::Blt, parse_label_or_imm),
+    (Instr::Bgt, parse_label_or_imm),
+    (Instr::Ble, parse_label_or_imm),
+    (Instr::Bal, parse_label_or_imm),
+    (Instr::B, parse_label_or_imm11),
 ];
 
-/// Generates a parser for parsing the instructions
-const fn generate_instructions_parser() -> fn(&str) -> IResult<&str, FullInstr, Err> {
-    move |input: &str| {
-        INSTRUCTIONS
-            .iter()
-            .flat_map(|(instr, parse_args)| {
-                instr
-                    .text_instruction()
-                    .iter()
-                    .map(|text_instr| (text_instr, instr, parse_args))
-                    .collect::<Vec<_>>()
-            })
-            .map(|(&text_instr, instr, args_parser)| {
-                map(
-                    tuple((value(instr, tag_no_case(text_instr)), args_parser)),
-                    |(instr, args)| FullInstr {
-                        instr: *instr,
-                        args,
-                    },
-                )
-            })
-            // we cannot nom::branch::alt here because it requires a tuple
-            // so we manually implement the alt combinator
-            .fold(
-                Err(nom::Err::Error(nom::error::ParseError::from_error_kind(
-                    input,
-                    ErrorKind::Alt,
-                ))),
-                |acc, mut f| match acc {
-                    Ok((i, o)) => Ok((i, o)),
-                    Err(_) => f(input),
-                },
-            )
+/// Parses a single instruction using the given instruction table.
+/// Every candidate is tried and the one consuming the most input wins,
+/// so that the order of the table does not matter in most cases.
+///
+/// Ties are broken by order: if several candidates consume the same amount of input,
+/// the one listed first in the table wins, and among the mnemonics of an instruction
+/// the one listed first in [`Instr::text_instruction`].
+/// When none of them succeeds, the failure found the furthest in the input is returned.
+fn parse_instr_from<'a>(
+    instructions: &[(Instr, ParseArgs)],
+    input: &'a str,
+) -> IResult<&'a str, FullInstr, Err<'a>> {
+    let mut best: Option<(&str, FullInstr)> = None;
+    let mut failure = None;
+    let remaining = |e: &Err| e.errors.first().map_or(usize::MAX, |(rest, _)| rest.len());
+    let mut error = nom::Err::Error(nom::error::ParseError::from_error_kind(
+        input,
+        ErrorKind::Alt,
+    ));
+
+    for (instr, parse_args) in instructions {
+        for text_instr in instr.text_instruction() {
+            match preceded(tag_no_case(*text_instr), parse_args)(input) {
+                Ok((rest, args)) => {
+                    // strictly less, so that the earlier candidate wins a tie
+                    let is_longer = best
+                        .as_ref()
+                        .is_none_or(|(best_rest, _)| rest.len() < best_rest.len());
+                    if is_longer {
+                        best = Some((
+                            rest,
+                            FullInstr {
+                                instr: *instr,
+                                args,
+                            },
+                        ));
+                    }
+                }
+                // a failure is more specific than whatever the other candidates report,
+                // and the one found the furthest in the input is the most specific of them
+                Err(nom::Err::Failure(e)) => {
+                    if failure
+                        .as_ref()
+                        .is_none_or(|f| remaining(&e) < remaining(f))
+                    {
+                        failure = Some(e);
+                    }
+                }
+                Err(e) => error = e,
+            }
+        }
     }
+
+    best.ok_or_else(|| failure.map_or(error, nom::Err::Failure))
+}
+
+/// Context of the failure raised on `mov rd, rm`
+const MOV_REGISTERS: &str = "mov registers";
+
+/// The registers of `mov rd, rm`
+fn parse_mov_registers(input: &str) -> IResult<&str, (Reg, Reg), Err<'_>> {
+    preceded(
+        pair(tag_no_case("mov"), space1),
+        pair(
+            preceded(parse_separator, Reg::parse),
+            preceded(parse_separator, Reg::parse),
+        ),
+    )(input)
 }
 
 /// Parses a single instruction.
-fn parse_instr(input: &str) -> IResult<&str, FullInstr, Err> {
-    const PARSE_INSTRUCTION: fn(&str) -> IResult<&str, FullInstr, Err> =
-        generate_instructions_parser();
-    PARSE_INSTRUCTION(input)
+/// `mov rd, rm` is rejected: it is the move that leaves the flags untouched,
+/// which has no encoding here, and assembling it as `movs` would change them.
+fn parse_instr(input: &str) -> IResult<&str, FullInstr, Err<'_>> {
+    if parse_mov_registers(input).is_ok() {
+        return Err(nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(MOV_REGISTERS))],
+        }));
+    }
+    parse_instr_from(INSTRUCTIONS, input)
 }
 
 /// Handles `.asciz` (alias `.string`)
-fn parse_string(input: &str) -> IResult<&str, String, Err> {
+fn parse_string(input: &str) -> IResult<&str, String, Err<'_>> {
     let prefix = pair(
         alt((tag_no_case(".string"), tag_no_case(".asciz"))),
         pair(take_till(|c| c == '"'), char('"')),
@@ -331,141 +772,888 @@ fn parse_string(input: &str) -> IResult<&str, String, Err> {
     )(input)
 }
 
-fn parse_comment(input: &str) -> IResult<&str, &str, Err> {
-    preceded(preceded(space0, char('@')), take_till(|c| c == '\n'))(input)
+fn parse_comment(input: &str) -> IResult<&str, &str, Err<'_>> {
+    preceded(preceded(space0, one_of("@;")), take_till(|c| c == '\n'))(input)
 }
 
-fn parse_end_of_line(input: &str) -> IResult<&str, (), Err> {
+fn parse_end_of_line(input: &str) -> IResult<&str, (), Err<'_>> {
     terminated(value((), space0), line_ending)(input)
 }
 
 /// clang emits push instructions that we don't support, so we just ignore them.
-fn parse_push(input: &str) -> IResult<&str, (), Err> {
+fn parse_push(input: &str) -> IResult<&str, (), Err<'_>> {
     value(
         (),
         delimited(tag_no_case("push"), take_till(|c| c == '\n'), line_ending),
     )(input)
 }
 
-fn parse_long(input: &str) -> IResult<&str, &str, Err> {
+/// clang may emit `it` blocks, which do not exist on our CPU: they are ignored.
+/// Handles `it` followed by up to three `t` or `e`, then the condition.
+fn parse_it(input: &str) -> IResult<&str, (), Err<'_>> {
+    value(
+        (),
+        tuple((
+            tag_no_case("it"),
+            take_while_m_n(0, 3, |c: char| matches!(c, 't' | 'e' | 'T' | 'E')),
+            space1,
+            take_till(|c| c == '\n'),
+        )),
+    )(input)
+}
+
+/// Context of the failure raised on `.arm` and `.code 32`
+const ARM_MODE: &str = "arm mode";
+
+/// Handles `.thumb` and `.code 16`, which change nothing, and rejects `.arm` and `.code 32`
+fn parse_instruction_set(input: &str) -> IResult<&str, (), Err<'_>> {
+    let directive = |name| terminated(tag_no_case(name), not(satisfy(is_label_char)));
+    let code = |bits| tuple((tag_no_case(".code"), space1, tag(bits), not(digit1)));
+
+    let (rest, thumb) = alt((
+        value(
+            true,
+            alt((recognize(directive(".thumb")), recognize(code("16")))),
+        ),
+        value(
+            false,
+            alt((recognize(directive(".arm")), recognize(code("32")))),
+        ),
+    ))(input)?;
+
+    if thumb {
+        Ok((rest, ()))
+    } else {
+        Err(nom::Err::Failure(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context(ARM_MODE))],
+        }))
+    }
+}
+
+fn parse_long(input: &str) -> IResult<&str, &str, Err<'_>> {
     preceded(pair(tag_no_case(".long"), space1), parse_label)(input)
 }
 
+/// Handles `.space` (aliases `.zero` and `.skip`), taking the number of zeroed bytes
+fn parse_space(input: &str) -> IResult<&str, usize, Err<'_>> {
+    preceded(
+        pair(
+            alt((
+                tag_no_case(".space"),
+                tag_no_case(".zero"),
+                tag_no_case(".skip"),
+            )),
+            space1,
+        ),
+        map(parse_number, |n| n as usize),
+    )(input)
+}
+
+/// Handles `.org`, taking the ROM address of the next instruction
+fn parse_org(input: &str) -> IResult<&str, usize, Err<'_>> {
+    preceded(
+        pair(tag_no_case(".org"), space1),
+        map(parse_number, |n| n as usize),
+    )(input)
+}
+
+/// Directives emitted by compilers that do not change the assembled program
+const IGNORED_DIRECTIVES: &[&str] = &[
+    ".addrsig",
+    ".addrsig_sym",
+    ".align",
+    ".arch",
+    ".balign",
+    ".cantunwind",
+    ".cpu",
+    ".eabi_attribute",
+    ".file",
+    ".fnend",
+    ".fnstart",
+    ".fpu",
+    ".hidden",
+    ".ident",
+    ".p2align",
+    ".pad",
+    ".save",
+    ".section",
+    ".setfp",
+    ".size",
+    ".syntax",
+    ".thumb_func",
+    ".type",
+    ".weak",
+];
+
+/// Any other directive, skipped up to the end of the line.
+/// Directives that are not in [`IGNORED_DIRECTIVES`] are kept so that they can be reported.
+fn parse_directive(input: &str) -> IResult<&str, ParsedLine, Err<'_>> {
+    map(
+        terminated(
+            recognize(pair(
+                char('.'),
+                take_while(|c: char| c.is_alphanumeric() || c == '_'),
+            )),
+            take_till(|c| c == '\n'),
+        ),
+        |name: &str| {
+            if IGNORED_DIRECTIVES
+                .iter()
+                .any(|ignored| ignored.eq_ignore_ascii_case(name))
+            {
+                ParsedLine::None
+            } else {
+                ParsedLine::UnknownDirective(name.to_owned())
+            }
+        },
+    )(input)
+}
+
+/// Handles `.globl` (alias `.global`)
+fn parse_global(input: &str) -> IResult<&str, &str, Err<'_>> {
+    preceded(
+        pair(alt((tag_no_case(".globl"), tag_no_case(".global"))), space1),
+        parse_label,
+    )(input)
+}
+
+/// The kind of section the following lines belong to.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Section {
+    /// Code, assembled into the ROM
+    Text,
+    /// Strings and other data, assembled into the RAM
+    Data,
+    /// Zeroed data, placed in the RAM after the other data
+    Bss,
+}
+
+impl Section {
+    fn from_name(name: &str) -> Self {
+        let name = name.trim_matches('"');
+        if name.starts_with(".text") {
+            Section::Text
+        } else if name.starts_with(".bss") {
+            Section::Bss
+        } else {
+            Section::Data
+        }
+    }
+}
+
+/// Handles `.text`, `.data`, `.bss` and `.section <name>, <flags>`
+fn parse_section(input: &str) -> IResult<&str, Section, Err<'_>> {
+    let end_of_name = not(satisfy(|c: char| {
+        c.is_alphanumeric() || c == '_' || c == '.'
+    }));
+
+    let named = preceded(
+        pair(tag_no_case(".section"), space1),
+        map(
+            take_till(|c: char| c == ',' || c.is_whitespace()),
+            Section::from_name,
+        ),
+    );
+    let shorthand = map(
+        terminated(
+            alt((
+                tag_no_case(".text"),
+                tag_no_case(".data"),
+                tag_no_case(".bss"),
+            )),
+            end_of_name,
+        ),
+        Section::from_name,
+    );
+
+    terminated(alt((named, shorthand)), take_till(|c| c == '\n'))(input)
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum ParsedLine {
     Instr(FullInstr),
     Label(String),
     String(String),
     Long(String),
+    Section(Section),
+    Global(String),
+    /// Moves the next instruction to the given ROM address
+    Org(usize),
+    /// The given number of zeroed bytes in the RAM
+    Space(usize),
+    /// A 32-bit constant of the literal pool, never produced by the parser
+    Literal(u32),
+    /// A directive we do not know about, ignored with a warning
+    UnknownDirective(String),
     None,
 }
 
+/// A parsed line along with the text it was parsed from.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct SourceLine {
+    /// Line number in the original input, starting at 1
+    pub number: usize,
+    /// The text that was parsed, after preprocessing
+    pub text: String,
+    /// The line of the original input, as written
+    pub original: String,
+    pub line: ParsedLine,
+}
+
+/// Options controlling how permissive the parser is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Require operands to be separated by commas
+    pub strict: bool,
+    /// Treat `;` as a statement separator instead of the start of a comment
+    pub multi_statement: bool,
+}
+
+/// Checks that the operands of an instruction are separated by commas,
+/// e.g. `adds r0, r1, r2` and not `adds r0 r1 r2`
+fn has_separating_commas(instr: &str) -> bool {
+    let operands = instr
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .map_or("", |(_, operands)| operands);
+
+    let mut in_operand = false;
+    let mut need_comma = false;
+    for c in operands.chars() {
+        match c {
+            ',' => {
+                in_operand = false;
+                need_comma = false;
+            }
+            ']' => {
+                in_operand = false;
+                need_comma = true;
+            }
+            c if c.is_whitespace() => {
+                need_comma |= in_operand;
+                in_operand = false;
+            }
+            _ if need_comma => return false,
+            // an opening bracket starts an operand but its content does not need a comma
+            '[' => {}
+            _ => in_operand = true,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+fn parse_line(input: &str) -> IResult<&str, ParsedLine, Err<'_>> {
+    parse_line_with(ParseOptions::default(), input)
+}
+
+/// Context of the failure raised on text following the operands of an instruction
+const TRAILING_TEXT: &str = "trailing text";
+
 /// Parses a single line of assembly code.
 /// A line can be an instruction, a label or a comment.
 /// If the line is not an instruction or a label, it is ignored.
-fn parse_line(input: &str) -> IResult<&str, ParsedLine, Err> {
+fn parse_line_with(options: ParseOptions, input: &str) -> IResult<&str, ParsedLine, Err<'_>> {
     if input.is_empty() {
         return Err(nom::Err::Error(nom::error::ParseError::from_error_kind(
             input,
             ErrorKind::Eof,
         )));
     }
+
+    let instr = |input| {
+        let (rest, (text, instr)) = preceded(space0, consumed(parse_instr))(input)?;
+        if options.strict && !has_separating_commas(text) {
+            return Err(nom::Err::Failure(VerboseError {
+                errors: vec![(
+                    input,
+                    VerboseErrorKind::Context("operands must be separated by commas"),
+                )],
+            }));
+        }
+        // only a comment may follow the operands
+        let trailing = rest.split('\n').next().unwrap_or_default().trim_start();
+        if !trailing.is_empty() && !trailing.starts_with(['@', ';']) {
+            return Err(nom::Err::Failure(VerboseError {
+                errors: vec![(
+                    &rest[rest.offset(trailing)..],
+                    VerboseErrorKind::Context(TRAILING_TEXT),
+                )],
+            }));
+        }
+        Ok((rest, ParsedLine::Instr(instr)))
+    };
+
     terminated(
         alt((
             map(preceded(space0, parse_label_definition), |s| {
                 ParsedLine::Label(s.to_owned())
             }),
-            map(preceded(space0, parse_instr), ParsedLine::Instr),
+            instr,
             map(preceded(space0, parse_string), ParsedLine::String),
             value(ParsedLine::None, parse_push),
+            value(ParsedLine::None, preceded(space0, parse_it)),
+            value(ParsedLine::None, preceded(space0, parse_instruction_set)),
             map(preceded(space0, parse_long), |str| {
                 ParsedLine::Long(str.to_owned())
             }),
+            map(preceded(space0, parse_section), ParsedLine::Section),
+            map(preceded(space0, parse_global), |str| {
+                ParsedLine::Global(str.to_owned())
+            }),
+            map(preceded(space0, parse_org), ParsedLine::Org),
+            map(preceded(space0, parse_space), ParsedLine::Space),
             value(ParsedLine::None, parse_comment),
             value(ParsedLine::None, multispace1),
-            // If something starts with a dot and is not a label, it's probably a directive we can ignore
-            value(
-                ParsedLine::None,
-                preceded(char('.'), take_till(|c| c == '\n')),
-            ),
+            preceded(space0, parse_directive),
         )),
         opt(parse_end_of_line),
     )(input)
 }
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ParseError {
     NomError {
+        line: usize,
         errors: Vec<(String, ErrorKind)>,
         json: String,
     },
+    InvalidRegister {
+        line: usize,
+        error: RegError,
+    },
+    Preprocess {
+        line: usize,
+        error: PreprocessError,
+    },
+    /// `.arm` or `.code 32`, we only assemble Thumb instructions
+    ArmMode {
+        line: usize,
+    },
+    /// An immediate written with `$` instead of `#`
+    DollarImmediate {
+        line: usize,
+        /// The immediate, without the `$`
+        immediate: String,
+    },
+    /// `adds rd, rn, #imm` or `subs rd, rn, #imm` with an immediate larger than 7
+    Imm3TooLarge {
+        line: usize,
+        instr: String,
+        rd: Reg,
+        value: u16,
+    },
+    /// Text after the operands of an instruction, like `movs r0, #5 xyz`
+    TrailingText {
+        line: usize,
+        text: String,
+    },
+    /// A negative immediate given to an instruction taking an unsigned one, like `movs r0, #-1`
+    NegativeImmediate {
+        line: usize,
+        instr: String,
+        /// The first operand, if it is a register
+        rd: Option<Reg>,
+        /// The opposite of the immediate
+        value: u32,
+    },
+    /// A number with a missing or invalid digit, like `0x` or `0b2`, or a leading zero, like `08`
+    InvalidNumber {
+        line: usize,
+        number: String,
+    },
+    /// An offset that is not a whole number of words, like `ldr r0, [sp, #6]`
+    UnalignedOffset {
+        line: usize,
+        error: ImmediateError,
+    },
+    /// A character literal outside of ASCII, like `movs r0, #'é'`
+    NonAsciiChar {
+        line: usize,
+        literal: char,
+    },
+    /// `mov rd, rm`, which does not set the flags, unlike `movs rd, rm`
+    MovRegisters {
+        line: usize,
+        rd: Reg,
+        rm: Reg,
+    },
 }
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParseError::NomError { errors, json } => {
-                writeln!(f, "Failed to parse assembly code:")?;
+            ParseError::NomError { line, errors, json } => {
+                writeln!(f, "Failed to parse assembly code on line {line}:")?;
                 for (line, error) in errors {
                     writeln!(f, "Error: {:?} at line: {}", error, line)?;
                 }
                 writeln!(f, "JSON: {}", json)
             }
+            ParseError::InvalidRegister { line, error } => {
+                write!(f, "Failed to parse assembly code on line {line}: {error}")
+            }
+            ParseError::Preprocess { line, error } => {
+                write!(f, "Failed to expand directive on line {line}: {error}")
+            }
+            ParseError::ArmMode { line } => write!(
+                f,
+                "Failed to parse assembly code on line {line}: ARM (32-bit) mode is not supported; \
+                 this assembler targets Thumb only"
+            ),
+            ParseError::DollarImmediate { line, immediate } => write!(
+                f,
+                "Failed to parse assembly code on line {line}: `${immediate}` is not an immediate, \
+                 use `#` for ARM immediates: `#{immediate}`"
+            ),
+            ParseError::Imm3TooLarge {
+                line,
+                instr,
+                rd,
+                value,
+            } => write!(
+                f,
+                "Failed to parse assembly code on line {line}: immediate #{value} is too large \
+                 for the 3 bits form of {instr}, did you mean `{instr} {rd}, #{value}` \
+                 (8 bits immediate, with rd == rn)?"
+            ),
+            ParseError::TrailingText { line, text } => write!(
+                f,
+                "Failed to parse assembly code on line {line}: unexpected `{text}` after the \
+                 instruction"
+            ),
+            ParseError::NegativeImmediate {
+                line,
+                instr,
+                rd,
+                value,
+            } => {
+                write!(
+                    f,
+                    "Failed to parse assembly code on line {line}: {instr} takes an unsigned \
+                     immediate, #-{value} cannot be encoded"
+                )?;
+                match (instr.as_str(), rd) {
+                    ("movs" | "mov", Some(rd)) if *value <= 255 => write!(
+                        f,
+                        ", use `movs {rd}, #{value}` then `rsbs {rd}, {rd}, #0`, \
+                         or `movs {rd}, #{}` then `mvns {rd}, {rd}`",
+                        value - 1
+                    ),
+                    ("adds" | "add" | "subs" | "sub", _) => {
+                        let opposite = match instr.as_str() {
+                            "adds" => "subs",
+                            "add" => "sub",
+                            "subs" => "adds",
+                            _ => "add",
+                        };
+                        write!(f, ", use `{opposite}` with #{value}")
+                    }
+                    _ => Ok(()),
+                }
+            }
+            ParseError::InvalidNumber { line, number } => {
+                write!(f, "Failed to parse assembly code on line {line}: ")?;
+                match number.get(..2).map(str::to_lowercase).as_deref() {
+                    Some("0x") => write!(f, "invalid hex digit in `{number}`"),
+                    Some("0b") => write!(f, "invalid binary digit in `{number}`"),
+                    _ => write!(
+                        f,
+                        "`{number}` starts with a zero, which other assemblers read as octal, \
+                         write it without the zero or with a `0x` prefix"
+                    ),
+                }
+            }
+            ParseError::UnalignedOffset { line, error } => {
+                write!(f, "Failed to parse assembly code on line {line}: {error}")
+            }
+            ParseError::NonAsciiChar { line, literal } => write!(
+                f,
+                "Failed to parse assembly code on line {line}: '{literal}' is not an ASCII \
+                 character, it has no single byte code"
+            ),
+            ParseError::MovRegisters { line, rd, rm } => write!(
+                f,
+                "Failed to parse assembly code on line {line}: `mov {rd}, {rm}` leaves the flags \
+                 untouched, which is not supported, use `movs {rd}, {rm}` if changing N and Z is fine"
+            ),
+        }
+    }
+}
+
+impl ParseError {
+    /// Builds the error from the preprocessed input and the source line of each of its lines.
+    pub(crate) fn from_nom_error(input: &str, line_numbers: &[usize], err: Err) -> Self {
+        let json = convert_error(input, err.clone());
+        let line = err
+            .errors
+            .first()
+            .map(|(rest, _)| line_numbers[input[..input.offset(rest)].matches('\n').count()])
+            .unwrap_or(1);
+
+        let invalid_register = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(INVALID_REGISTER) => {
+                let number = rest[1..].split(|c: char| !c.is_ascii_digit()).next()?;
+                Reg::try_from(number.parse::<u8>().ok()?).err()
+            }
+            VerboseErrorKind::Context(HIGH_REGISTER) => Reg::parse(rest)
+                .ok()
+                .map(|(_, reg)| RegError::HighRegister(reg)),
+            _ => None,
+        });
+        if let Some(error) = invalid_register {
+            return Self::InvalidRegister { line, error };
+        }
+
+        let arm_mode = err
+            .errors
+            .iter()
+            .any(|(_, kind)| matches!(kind, VerboseErrorKind::Context(ARM_MODE)));
+        if arm_mode {
+            return Self::ArmMode { line };
+        }
+
+        let dollar = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(DOLLAR_IMMEDIATE) => Some(rest),
+            _ => None,
+        });
+        if let Some(rest) = dollar {
+            let immediate = rest[1..]
+                .split(|c: char| c.is_whitespace() || c == ',' || c == ']')
+                .next()
+                .unwrap_or_default();
+            return Self::DollarImmediate {
+                line,
+                immediate: immediate.to_owned(),
+            };
+        }
+
+        let imm3_too_large = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(IMM3_TOO_LARGE) => {
+                let before = &input[..input.offset(rest)];
+                let instr = before.rsplit('\n').next()?.split_whitespace().last()?;
+                let (_, (rd, _, imm)) = parse_rd_rn_imm8_operands(rest).ok()?;
+                Some((instr.to_lowercase(), rd, imm.0))
+            }
+            _ => None,
+        });
+        if let Some((instr, rd, value)) = imm3_too_large {
+            return Self::Imm3TooLarge {
+                line,
+                instr,
+                rd,
+                value,
+            };
+        }
+
+        let trailing = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(TRAILING_TEXT) => rest.lines().next(),
+            _ => None,
+        });
+        if let Some(text) = trailing {
+            return Self::TrailingText {
+                line,
+                text: text.trim_end().to_owned(),
+            };
+        }
+
+        let negative = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(NEGATIVE_IMMEDIATE) => {
+                let before = &input[..input.offset(rest)];
+                let (instr, operands) = before
+                    .rsplit('\n')
+                    .next()?
+                    .trim_start()
+                    .split_once(char::is_whitespace)?;
+                let rd = operands
+                    .split(',')
+                    .next()
+                    .and_then(|rd| Reg::parse(rd.trim()).ok());
+                let (_, value) = preceded(tag("#-"), parse_number)(rest).ok()?;
+                Some((instr.to_lowercase(), rd.map(|(_, rd)| rd), value))
+            }
+            _ => None,
+        });
+        if let Some((instr, rd, value)) = negative {
+            return Self::NegativeImmediate {
+                line,
+                instr,
+                rd,
+                value,
+            };
+        }
+
+        let invalid_number = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(INVALID_NUMBER) => {
+                rest.split(|c: char| !c.is_ascii_alphanumeric()).next()
+            }
+            _ => None,
+        });
+        if let Some(number) = invalid_number {
+            return Self::InvalidNumber {
+                line,
+                number: number.to_owned(),
+            };
+        }
+
+        let unaligned = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(UNALIGNED_OFFSET) => {
+                parse_unsigned_value(rest).ok().map(|(_, value)| value)
+            }
+            _ => None,
+        });
+        if let Some(value) = unaligned {
+            return Self::UnalignedOffset {
+                line,
+                error: ImmediateError::UnalignedOffset {
+                    value,
+                    alignment: 4,
+                },
+            };
+        }
+
+        let non_ascii = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(NON_ASCII_CHAR) => {
+                let text = rest[1..].split('\'').next()?;
+                unescape_string(text).chars().next()
+            }
+            _ => None,
+        });
+        if let Some(literal) = non_ascii {
+            return Self::NonAsciiChar { line, literal };
+        }
+
+        let mov = err.errors.iter().find_map(|(rest, kind)| match kind {
+            VerboseErrorKind::Context(MOV_REGISTERS) => {
+                parse_mov_registers(rest).ok().map(|(_, regs)| regs)
+            }
+            _ => None,
+        });
+        if let Some((rd, rm)) = mov {
+            return Self::MovRegisters { line, rd, rm };
+        }
+
+        let errors = err
+            .errors
+            .into_iter()
+            .map(|(input, kind)| {
+                let kind = match kind {
+                    VerboseErrorKind::Nom(nom_kind) => nom_kind,
+                    _ => ErrorKind::Fail,
+                };
+                (input.lines().next().unwrap_or_default().to_owned(), kind)
+            })
+            .collect();
+
+        Self::NomError { line, errors, json }
+    }
+
+    /// Line of the source the error was found on, starting at 1.
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::NomError { line, .. }
+            | ParseError::InvalidRegister { line, .. }
+            | ParseError::Preprocess { line, .. }
+            | ParseError::ArmMode { line }
+            | ParseError::DollarImmediate { line, .. }
+            | ParseError::Imm3TooLarge { line, .. }
+            | ParseError::TrailingText { line, .. }
+            | ParseError::NegativeImmediate { line, .. }
+            | ParseError::InvalidNumber { line, .. }
+            | ParseError::UnalignedOffset { line, .. }
+            | ParseError::NonAsciiChar { line, .. }
+            | ParseError::MovRegisters { line, .. } => *line,
+        }
+    }
+
+    pub(crate) fn line_mut(&mut self) -> &mut usize {
+        match self {
+            ParseError::NomError { line, .. }
+            | ParseError::InvalidRegister { line, .. }
+            | ParseError::Preprocess { line, .. }
+            | ParseError::ArmMode { line }
+            | ParseError::DollarImmediate { line, .. }
+            | ParseError::Imm3TooLarge { line, .. }
+            | ParseError::TrailingText { line, .. }
+            | ParseError::NegativeImmediate { line, .. }
+            | ParseError::InvalidNumber { line, .. }
+            | ParseError::UnalignedOffset { line, .. }
+            | ParseError::NonAsciiChar { line, .. }
+            | ParseError::MovRegisters { line, .. } => line,
+        }
+    }
+}
+
+/// Puts each `;` separated statement on its own line.
+/// Strings, character literals and the comment after an `@` are left untouched.
+fn split_statements(line: &str) -> String {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut output = String::with_capacity(line.len());
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '@') => {
+                output.push_str(&line[i..]);
+                break;
+            }
+            (None, ';') => {
+                output.push('\n');
+                continue;
+            }
+            _ => {}
         }
+        escaped = c == '\\' && !escaped;
+        output.push(c);
     }
+    output
 }
 
-impl ParseError {
-    pub fn from_nom_error(input: &str, err: Err) -> Self {
-        let json = convert_error(input, err.clone());
-
-        let errors = err
-            .errors
-            .into_iter()
-            .map(|(input, kind)| {
-                let kind = match kind {
-                    nom::error::VerboseErrorKind::Nom(nom_kind) => nom_kind,
-                    _ => ErrorKind::Fail,
-                };
-                (input.lines().next().unwrap_or_default().to_owned(), kind)
-            })
-            .collect();
-
-        Self::NomError { errors, json }
+/// Splits a line into the parts outside and inside of string literals, quotes included in the latter.
+/// The comment after an unquoted `@` or `;` is kept whole and counts as a string,
+/// the statements separated by `;` having already been split when they are enabled.
+/// Returns each part along with whether it is a string.
+fn split_strings(line: &str) -> Vec<(&str, bool)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if c == '"' && !escaped {
+            // the closing quote belongs to the string, the opening one too
+            let end = if in_string { i + 1 } else { i };
+            parts.push((&line[start..end], in_string));
+            start = end;
+            in_string = !in_string;
+        } else if matches!(c, '@' | ';') && !in_string {
+            parts.push((&line[start..i], false));
+            parts.push((&line[i..], true));
+            return parts;
+        }
+        escaped = c == '\\' && !escaped;
     }
+    parts.push((&line[start..], in_string));
+    parts
 }
 
-fn preprocess(input: &str) -> String {
+/// Rewrites the lines we do not support directly.
+/// Returns the new text along with the original line number of each of its lines.
+fn preprocess(input: &str, options: ParseOptions) -> Result<(String, Vec<usize>), ParseError> {
     const REPLACEMENTS: [(&str, &str); 2] = [
-        (r#"movs?\s+(r\d), (r\d)"#, "lsls $1, $2, #0"),
         // let's hope nobody uses r6
         (
-            r#"ldrb\s+(r\d), \[(r\d), (r\d)\]"#,
+            r#"(?i)ldrb\s+(r\d), \[(r\d), (r\d)\]"#,
             "adds r6, $2, $3\nldrb $1, [r6]",
         ),
+        // there is no immediate form of these, r6 holds the immediate instead
+        (
+            r#"(?i)\b(ands|orrs|eors)\s+(r[0-57]),\s*(#[^@;]*)"#,
+            "movs r6, $3\n$1 $2, r6",
+        ),
     ];
 
-    let mut output = input.to_owned();
-
-    for (pattern, replacement) in REPLACEMENTS.iter() {
-        let re = Regex::new(pattern).unwrap();
-        output = re.replace_all(&output, *replacement).to_string();
+    let regexes: Vec<_> = REPLACEMENTS
+        .iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), *replacement))
+        .collect();
+
+    let mut output = String::with_capacity(input.len());
+    let mut line_numbers = Vec::new();
+
+    let lines = input
+        .split('\n')
+        .enumerate()
+        // CRLF line endings are normalized, a line cannot contain a raw carriage return otherwise
+        .map(|(i, line)| (i + 1, line.strip_suffix('\r').unwrap_or(line).to_owned()))
+        .collect();
+    let lines = preprocess::expand(lines)
+        .map_err(|(line, error)| ParseError::Preprocess { line, error })?;
+
+    for (i, (number, line)) in lines.into_iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        let mut line = if options.multi_statement {
+            split_statements(&line)
+        } else {
+            line
+        };
+        // the content of strings is left as is
+        line = split_strings(&line)
+            .into_iter()
+            .map(|(part, is_string)| {
+                let mut part = part.to_owned();
+                if !is_string {
+                    for (re, replacement) in regexes.iter() {
+                        part = re.replace_all(&part, *replacement).to_string();
+                    }
+                }
+                part
+            })
+            .collect();
+        // a replacement can span several lines
+        line_numbers.extend(std::iter::repeat_n(number, line.matches('\n').count() + 1));
+        output.push_str(&line);
     }
-    output
+    Ok((output, line_numbers))
 }
+
 pub fn parse_lines(input: &str) -> Result<Vec<ParsedLine>, ParseError> {
-    let input = preprocess(input);
+    parse_lines_with(input, ParseOptions::default())
+}
+
+/// Same as [`parse_lines`], but rejects operands that are not separated by commas.
+pub fn parse_lines_strict(input: &str) -> Result<Vec<ParsedLine>, ParseError> {
+    parse_lines_with(
+        input,
+        ParseOptions {
+            strict: true,
+            ..Default::default()
+        },
+    )
+}
 
-    let res = many_till(parse_line, eof)(input.as_ref())
+/// Same as [`parse_lines`], with the given options.
+pub fn parse_lines_with(input: &str, options: ParseOptions) -> Result<Vec<ParsedLine>, ParseError> {
+    parse_source_with(input, options).map(|lines| lines.into_iter().map(|l| l.line).collect())
+}
+
+/// Same as [`parse_lines_with`], but keeps the text each line was parsed from.
+pub(crate) fn parse_source_with(
+    input: &str,
+    options: ParseOptions,
+) -> Result<Vec<SourceLine>, ParseError> {
+    let original: Vec<_> = input.split('\n').map(str::trim).collect();
+    let (input, line_numbers) = preprocess(input, options)?;
+    let input: &str = input.as_ref();
+
+    // skip the leading whitespace, which may span several lines
+    let line_of = |text: &str| {
+        let start = input.offset(text) + (text.len() - text.trim_start().len());
+        line_numbers[input[..start].matches('\n').count()]
+    };
+
+    let res = many_till(consumed(|i| parse_line_with(options, i)), eof)(input)
         .finish()
         .map(|(_, (lines, _))| lines)
         .map(|lines| {
             lines
                 .into_iter()
-                .filter(|l| l != &ParsedLine::None)
+                .filter(|(_, l)| l != &ParsedLine::None)
+                .map(|(text, line)| {
+                    let number = line_of(text);
+                    SourceLine {
+                        number,
+                        text: text.trim().to_owned(),
+                        original: original[number - 1].to_owned(),
+                        line,
+                    }
+                })
                 .collect()
         })
-        .map_err(|e| ParseError::from_nom_error(input.as_ref(), e));
+        .map_err(|e| ParseError::from_nom_error(input, &line_numbers, e));
 
     res
 }
@@ -488,6 +1676,18 @@ mod tests {
         assert_eq!(res.1, expected);
     }
 
+    #[test]
+    fn tie_goes_to_the_first_candidate() {
+        let table: &[(Instr, ParseArgs)] =
+            &[(Instr::Adds2, parse_rd_imm8), (Instr::Adds3, parse_rd_imm8)];
+        let first = parse_instr_from(table, "adds r0, #1").unwrap().1;
+        assert_eq!(first.instr, Instr::Adds2);
+
+        let reversed = [table[1], table[0]];
+        let first = parse_instr_from(&reversed, "adds r0, #1").unwrap().1;
+        assert_eq!(first.instr, Instr::Adds3);
+    }
+
     #[test]
     fn lsrs() {
         let input = "lsrs r2, r5, #9";
@@ -743,6 +1943,16 @@ mod tests {
         assert_eq!(expected, res.1);
     }
 
+    #[test]
+    fn ldr_pc() {
+        let expected = ParsedLine::Instr(FullInstr {
+            instr: Instr::LdrPc,
+            args: Args::RtPcImm8W(Reg::R0, Immediate8W::new(8).unwrap()),
+        });
+        assert_eq!(parse_line("ldr r0, [pc, #8]").unwrap().1, expected);
+        assert!(parse_line("str r0, [pc, #8]").is_err());
+    }
+
     #[test]
     fn sub() {
         let input = r#"
@@ -813,6 +2023,365 @@ run:
         assert_eq!(actual.1, expected);
     }
 
+    #[test]
+    fn strings_are_not_rewritten() {
+        for text in ["movs r0, r1", "ldrb r0, [r1, r2]", "ands r0, #1"] {
+            let input = format!(".asciz \"{text}\"");
+            let expected = ParsedLine::String(text.to_owned());
+            assert_eq!(parse_lines(&input).unwrap(), vec![expected], "{input}");
+        }
+
+        let (output, _) = preprocess(
+            ".asciz \"ldrb r0, [r1, r2]\" @ ldrb r0, [r1, r2]",
+            ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(output, ".asciz \"ldrb r0, [r1, r2]\" @ ldrb r0, [r1, r2]");
+
+        let (output, _) = preprocess(
+            "ldrb r0, [r1, r2] @ \"ldrb r3, [r4, r5]\"",
+            ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "adds r6, r1, r2\nldrb r0, [r6] @ \"ldrb r3, [r4, r5]\""
+        );
+    }
+
+    #[test]
+    fn uppercase() {
+        let input = "
+            MOVS R0, #1
+            ADD SP, #4
+            LDR R1, [SP, #8]
+            STR R2, [SP]
+            LDRB R3, [R4, #1]
+            BNE .Label
+        ";
+        let expected = vec![
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RdImm8(Reg::R0, Immediate8::new(1).unwrap()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::AddSp,
+                args: Args::Immediate7W(Immediate7W::new(4).unwrap()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Ldr,
+                args: Args::RtSpImm8W(Reg::R1, Immediate8W::new(8).unwrap()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Str,
+                args: Args::RtSpImm8W(Reg::R2, Immediate8W::new(0).unwrap()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Ldr2,
+                args: Args::RtRnImm5(Reg::R3, Reg::R4, Immediate5::new(1).unwrap()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Bne,
+                args: Args::Label(".Label".to_owned()),
+            }),
+        ];
+        let res = parse_lines(input).unwrap();
+        assert_eq!(expected, res);
+    }
+
+    #[test]
+    fn uppercase_preprocessed() {
+        let input = "
+            MOVS R0, R1
+            LDRB R2, [R3, R4]
+        ";
+        let expected = parse_lines(&input.to_lowercase()).unwrap();
+        let res = parse_lines(input).unwrap();
+        assert_eq!(expected, res);
+        assert_eq!(res.len(), 3);
+    }
+
+    #[test]
+    fn movs_registers() {
+        let expected = vec![ParsedLine::Instr(FullInstr {
+            instr: Instr::Movs2,
+            args: Args::TwoRegs(Reg::R0, Reg::R1),
+        })];
+        assert_eq!(parse_lines("movs r0, r1").unwrap(), expected);
+        assert!(parse_lines("movs r0, sp").is_err());
+        assert!(matches!(
+            parse_lines("mov r2, r3"),
+            Err(ParseError::MovRegisters {
+                line: 1,
+                rd: Reg::R2,
+                rm: Reg::R3
+            })
+        ));
+    }
+
+    #[test]
+    fn uppercase_special_registers() {
+        assert_eq!(Reg::parse("SP").unwrap().1, Reg::SP);
+        assert_eq!(Reg::parse("Pc").unwrap().1, Reg::PC);
+        assert_eq!(Reg::parse("R7").unwrap().1, Reg::R7);
+    }
+
+    #[test]
+    fn cmp_two_regs() {
+        let res = parse_instr("cmp r0, r1").unwrap();
+        let expected = FullInstr {
+            instr: Instr::Cmp,
+            args: Args::TwoRegs(Reg::R0, Reg::R1),
+        };
+        assert_eq!(res.1, expected);
+    }
+
+    #[test]
+    fn cmp_imm8() {
+        let res = parse_instr("cmp r0, #5").unwrap();
+        let expected = FullInstr {
+            instr: Instr::Cmp2,
+            args: Args::RdImm8(Reg::R0, Immediate8::new(5).unwrap()),
+        };
+        assert_eq!(res.1, expected);
+    }
+
+    #[test]
+    fn two_regs_rejects_immediate() {
+        assert!(parse_two_regs(" r0, #5").is_err());
+    }
+
+    #[test]
+    fn flag_only_reject_destination() {
+        for input in ["cmp r0, r1, r2", "cmn r0, r1, r2", "tst r0, r1, r2"] {
+            assert!(parse_lines(input).is_err(), "{input} should not parse");
+        }
+    }
+
+    #[test]
+    fn table_order_independent() {
+        let mut reversed = INSTRUCTIONS.to_vec();
+        reversed.reverse();
+
+        for input in [
+            "add sp, #4",
+            "adds r0, r1, r2",
+            "adds r0, r1, #2",
+            "adds r0, #2",
+            "lsls r0, r1",
+            "lsls r0, r1, #4",
+            "cmp r0, r1",
+            "cmp r0, #5",
+            "rsbs r0, r1, #0",
+            "ldr r0, [sp, #4]",
+            "ldr r0, [r1, #4]",
+            "ldr r0, .label",
+            "bics r0, r1",
+        ] {
+            let expected = parse_instr(input).unwrap();
+            let actual = parse_instr_from(&reversed, input).unwrap();
+            assert_eq!(expected, actual, "{input}");
+            assert!(actual.0.is_empty(), "{input}");
+        }
+    }
+
+    #[test]
+    fn missing_commas() {
+        let input = "adds r0 r1 r2";
+        let expected = vec![ParsedLine::Instr(FullInstr {
+            instr: Instr::Adds,
+            args: Args::RdRnRm(Reg::R0, Reg::R1, Reg::R2),
+        })];
+
+        assert_eq!(parse_lines(input).unwrap(), expected);
+        assert!(parse_lines_strict(input).is_err());
+    }
+
+    #[test]
+    fn strict() {
+        let input = "
+            adds r0, r1, r2
+            ldr r0, [sp, #4]
+            str r1, [sp]
+            ldrb r2, [r3, #1] @ comment
+            b .label
+        ";
+        assert_eq!(
+            parse_lines_strict(input).unwrap(),
+            parse_lines(input).unwrap()
+        );
+
+        for input in ["ldr r0, [sp #4]", "ldr r0 [r1]", "cmp r0 #1"] {
+            assert!(parse_lines(input).is_ok(), "{input}");
+            assert!(parse_lines_strict(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn halfword() {
+        let res = parse_line("ldrh r0, [r1, #4]").unwrap().1;
+        let expected = ParsedLine::Instr(FullInstr {
+            instr: Instr::Ldrh,
+            args: Args::RtRnImm5H(Reg::R0, Reg::R1, Immediate5::new(2).unwrap()),
+        });
+        assert_eq!(res, expected);
+
+        let res = parse_line("strh r2, [r3]").unwrap().1;
+        let expected = ParsedLine::Instr(FullInstr {
+            instr: Instr::Strh,
+            args: Args::RtRnImm5H(Reg::R2, Reg::R3, Immediate5::new(0).unwrap()),
+        });
+        assert_eq!(res, expected);
+
+        // not a multiple of 2, or too far
+        assert!(parse_lines("ldrh r0, [r1, #3]").is_err());
+        assert!(parse_lines("ldrh r0, [r1, #64]").is_err());
+        assert!(parse_lines("ldrh r0, [r1, #62]").is_ok());
+    }
+
+    #[test]
+    fn crlf() {
+        let input = "movs r0, #1 @ comment\r\n.str:\r\n.asciz \"Hi\"\r\n";
+        let expected = vec![
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RdImm8(Reg::R0, Immediate8::new(1).unwrap()),
+            }),
+            ParsedLine::Label(".str".to_owned()),
+            ParsedLine::String("Hi".to_owned()),
+        ];
+        assert_eq!(parse_lines(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn it_block() {
+        for input in ["it eq", "itt eq", "ITE ne", "itete gt @ comment"] {
+            assert_eq!(parse_line(input).unwrap().1, ParsedLine::None, "{input}");
+        }
+        assert!(parse_lines("ittttt eq").is_err());
+    }
+
+    #[test]
+    fn dotted_labels() {
+        let input = "
+            .L.str.1:
+            foo$bar:
+            b .L.str.1
+            bne foo$bar@ comment
+        ";
+        let expected = vec![
+            ParsedLine::Label(".L.str.1".to_owned()),
+            ParsedLine::Label("foo$bar".to_owned()),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::B,
+                args: Args::Label(".L.str.1".to_owned()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Bne,
+                args: Args::Label("foo$bar".to_owned()),
+            }),
+        ];
+        assert_eq!(parse_lines(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn dotted_long() {
+        let res = parse_line(".long .L.str.1").unwrap();
+        assert_eq!(res, ("", ParsedLine::Long(".L.str.1".to_owned())));
+    }
+
+    #[test]
+    fn semicolon() {
+        let input = "movs r0, #1; movs r1, #2";
+        let first = ParsedLine::Instr(FullInstr {
+            instr: Instr::Movs,
+            args: Args::RdImm8(Reg::R0, Immediate8::new(1).unwrap()),
+        });
+        let second = ParsedLine::Instr(FullInstr {
+            instr: Instr::Movs,
+            args: Args::RdImm8(Reg::R1, Immediate8::new(2).unwrap()),
+        });
+
+        assert_eq!(parse_lines(input).unwrap(), vec![first.clone()]);
+
+        let options = ParseOptions {
+            multi_statement: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_lines_with(input, options).unwrap(),
+            vec![first, second]
+        );
+    }
+
+    #[test]
+    fn semicolon_in_string() {
+        let options = ParseOptions {
+            multi_statement: true,
+            ..Default::default()
+        };
+        let res = parse_lines_with(r#".asciz "a;b""#, options).unwrap();
+        assert_eq!(res, vec![ParsedLine::String("a;b".to_owned())]);
+    }
+
+    #[test]
+    fn semicolon_in_comment_or_character() {
+        let options = ParseOptions {
+            multi_statement: true,
+            ..Default::default()
+        };
+        let movs = |rd, imm| {
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RdImm8(rd, Immediate8::new(imm).unwrap()),
+            })
+        };
+
+        let res = parse_lines_with("movs r0, #1 @ a; b", options).unwrap();
+        assert_eq!(res, vec![movs(Reg::R0, 1)]);
+
+        let res = parse_lines_with("movs r0, #';'; movs r1, #2", options).unwrap();
+        assert_eq!(res, vec![movs(Reg::R0, 59), movs(Reg::R1, 2)]);
+    }
+
+    #[test]
+    fn sections() {
+        let input = "
+            .text
+            .section .rodata.str1.1,\"aMS\",%progbits,1
+            .data
+            .section .text.startup
+            .bss
+            .section .bss.buffer,\"aw\",%nobits
+            .textual
+        ";
+        let expected = vec![
+            ParsedLine::Section(Section::Text),
+            ParsedLine::Section(Section::Data),
+            ParsedLine::Section(Section::Data),
+            ParsedLine::Section(Section::Text),
+            ParsedLine::Section(Section::Bss),
+            ParsedLine::Section(Section::Bss),
+            ParsedLine::UnknownDirective(".textual".to_owned()),
+        ];
+        let res = parse_lines(input).unwrap();
+        assert_eq!(expected, res);
+    }
+
+    #[test]
+    fn global() {
+        let input = "
+            .globl run
+            .global main
+        ";
+        let expected = vec![
+            ParsedLine::Global("run".to_owned()),
+            ParsedLine::Global("main".to_owned()),
+        ];
+        let res = parse_lines(input).unwrap();
+        assert_eq!(expected, res);
+    }
+
     #[test]
     fn long() {
         let input = ".long .L0";
@@ -823,4 +2392,157 @@ run:
 
         assert_eq!(actual.1, expected);
     }
+
+    #[test]
+    fn org() {
+        assert_eq!(parse_line(".org 16").unwrap().1, ParsedLine::Org(16));
+        assert_eq!(parse_line(".org 0x10").unwrap().1, ParsedLine::Org(16));
+    }
+
+    #[test]
+    fn space() {
+        assert_eq!(parse_line(".space 16").unwrap().1, ParsedLine::Space(16));
+        assert_eq!(parse_line(".zero 0x4").unwrap().1, ParsedLine::Space(4));
+        assert_eq!(parse_line(".skip 2").unwrap().1, ParsedLine::Space(2));
+    }
+
+    #[test]
+    fn add_sp_reg() {
+        let expected = FullInstr {
+            instr: Instr::AddSpReg,
+            args: Args::RdSpImm8W(Reg::R0, Immediate8W::new(8).unwrap()),
+        };
+        assert_eq!(parse_instr("add r0, sp, #8").unwrap().1, expected);
+    }
+
+    #[test]
+    fn fixed_point() {
+        assert_eq!(parse_fixed_point("Q8(1.5)").unwrap().1, 384);
+        assert_eq!(parse_fixed_point("Q4(0.5)").unwrap().1, 8);
+        assert_eq!(parse_fixed_point("q2(3)").unwrap().1, 12);
+        assert!(parse_fixed_point("Q16(1.0)").is_err());
+
+        let expected = FullInstr {
+            instr: Instr::Movs,
+            args: Args::RdImm8(Reg::R0, Immediate8::new(8).unwrap()),
+        };
+        assert_eq!(parse_instr("movs r0, #Q4(0.5)").unwrap().1, expected);
+    }
+
+    #[test]
+    fn spaces_in_brackets() {
+        let expected = FullInstr {
+            instr: Instr::Ldr,
+            args: Args::RtSpImm8W(Reg::R0, Immediate8W::new(4).unwrap()),
+        };
+        assert_eq!(parse_instr("ldr r0, [ sp, #4 ]").unwrap().1, expected);
+        assert_eq!(parse_instr("ldr r0, [\tsp ,\t#4\t]").unwrap().1, expected);
+
+        let expected = FullInstr {
+            instr: Instr::Ldr2,
+            args: Args::RtRnImm5(Reg::R0, Reg::R1, Immediate5::new(2).unwrap()),
+        };
+        assert_eq!(parse_instr("ldr r0, [r1 , #2]").unwrap().1, expected);
+        assert_eq!(parse_instr("ldr r0, [ r1, #2 ]").unwrap().1, expected);
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(parse_parenthesized("(1<<4)").unwrap().1, 16);
+        assert_eq!(parse_parenthesized("(2*3+1)").unwrap().1, 7);
+        assert_eq!(
+            parse_parenthesized("( 2 * (3 + 1) - 0x10 / 4 )").unwrap().1,
+            4
+        );
+        assert_eq!(parse_parenthesized("(-3 + 5)").unwrap().1, 2);
+        assert!(parse_parenthesized("(1 / 0)").is_err());
+
+        let expected = FullInstr {
+            instr: Instr::Movs,
+            args: Args::RdImm8(Reg::R0, Immediate8::new(33).unwrap()),
+        };
+        assert_eq!(parse_instr("movs r0, #(4*8+1)").unwrap().1, expected);
+        assert!(parse_instr("movs r0, #(1 << 9)").is_err());
+    }
+
+    #[test]
+    fn instruction_set() {
+        for directive in [".thumb", ".code 16", "  .CODE  16", ".thumb_func"] {
+            assert_eq!(
+                parse_line(directive).unwrap().1,
+                ParsedLine::None,
+                "{directive}"
+            );
+        }
+        assert_eq!(
+            parse_line(".armv6").unwrap().1,
+            ParsedLine::UnknownDirective(".armv6".to_owned())
+        );
+        for directive in [".arm", ".code 32", "  .ARM"] {
+            assert!(
+                matches!(parse_line(directive), Err(nom::Err::Failure(_))),
+                "{directive}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_instruction_is_listed() {
+        for (instr, _) in INSTRUCTIONS {
+            assert!(Instr::ALL.contains(instr), "{instr:?}");
+        }
+    }
+
+    #[test]
+    fn shift_forms() {
+        for (mnemonic, immediate, register) in [
+            ("lsls", Instr::Lsls, Instr::Lsls2),
+            ("lsrs", Instr::Lsrs, Instr::Lsrs2),
+            ("asrs", Instr::Asrs, Instr::Asrs2),
+            ("lsl", Instr::Lsls, Instr::Lsls2),
+            ("lsr", Instr::Lsrs, Instr::Lsrs2),
+            ("asr", Instr::Asrs, Instr::Asrs2),
+        ] {
+            let parsed = parse_instr(&format!("{mnemonic} r0, r1, #4")).unwrap().1;
+            assert_eq!(parsed.instr, immediate, "{mnemonic}");
+            assert_eq!(
+                parsed.args,
+                Args::RdRmImm5(Reg::R0, Reg::R1, Immediate5::new(4).unwrap())
+            );
+
+            let parsed = parse_instr(&format!("{mnemonic} r0, r1")).unwrap().1;
+            assert_eq!(parsed.instr, register, "{mnemonic}");
+            assert_eq!(parsed.args, Args::TwoRegs(Reg::R0, Reg::R1));
+        }
+    }
+
+    #[test]
+    fn hex_immediate() {
+        assert_eq!(
+            Immediate8::parse("#0xff").unwrap().1,
+            Immediate8::new(255).unwrap()
+        );
+        assert!(Immediate8::parse("#0x100").is_err());
+    }
+
+    #[test]
+    fn char_literal() {
+        assert_eq!(parse_char_literal("'A'").unwrap().1, 65);
+        assert_eq!(parse_char_literal(r"'\n'").unwrap().1, 10);
+        assert_eq!(parse_char_literal(r"'\\'").unwrap().1, 92);
+        assert!(matches!(
+            parse_char_literal(r"'é'"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(parse_char_literal(r"'\u007f'").is_ok());
+        assert!(parse_char_literal(r"'\u0080'").is_err());
+        assert!(parse_char_literal("''").is_err());
+        assert!(parse_char_literal("'ab'").is_err());
+
+        let expected = FullInstr {
+            instr: Instr::Movs,
+            args: Args::RdImm8(Reg::R0, Immediate8::new(66).unwrap()),
+        };
+        assert_eq!(parse_instr("movs r0, #('A' + 1)").unwrap().1, expected);
+    }
 }
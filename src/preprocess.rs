@@ -0,0 +1,689 @@
+//! Directives working on the text of the program, expanded before parsing.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::parser::is_label_char;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum PreprocessError {
+    #[error(".if without a matching .endif")]
+    UnterminatedIf,
+    #[error("{0} without a matching .if")]
+    UnmatchedDirective(&'static str),
+    #[error("Second .else for the .if on line {0}")]
+    DuplicateElse(usize),
+    #[error("Symbol {0} is not defined")]
+    UnknownSymbol(String),
+    #[error("Invalid value {0:?}")]
+    InvalidValue(String),
+    #[error(".macro without a matching .endm")]
+    UnterminatedMacro,
+    #[error(".endm without a matching .macro")]
+    UnmatchedEndm,
+    #[error(".rept without a matching .endr")]
+    UnterminatedRept,
+    #[error(".endr without a matching .rept")]
+    UnmatchedEndr,
+    #[error("Macro {0} cannot be defined inside another macro")]
+    NestedMacro(String),
+    #[error("Macro {0} cannot invoke itself")]
+    RecursiveMacro(String),
+    #[error("Macro {name} takes {expected} arguments, {found} given")]
+    MacroArguments {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum IncludeError {
+    #[error("Expected a quoted path, found {0:?}")]
+    InvalidPath(String),
+    #[error("{0} includes itself")]
+    Cycle(String),
+    #[error("Including {0} exceeds the maximum depth of {MAX_INCLUDE_DEPTH}")]
+    TooDeep(String),
+    #[error("Could not read {path}: {reason}")]
+    Unreadable { path: String, reason: String },
+}
+
+/// Maximum number of nested `.include`
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Inlines the files referenced by `.include "path"`.
+/// Paths are relative to the directory of the including file.
+///
+/// # Arguments
+///
+/// * `input`: The content of the file at `path`.
+/// * `path`: The path of the input, only used to locate the included files.
+/// * `resolve`: Returns the content of the file at the given path.
+///
+/// returns: The input with every `.include` replaced by the content of the file.
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+///
+/// let output = parm_assembler::expand_includes(".include \"a.s\"", Path::new("main.s"), |path| {
+///     match path.to_str() {
+///         Some("a.s") => Ok("movs r0, #1".to_owned()),
+///         _ => Err("not found"),
+///     }
+/// });
+///
+/// assert_eq!(output.unwrap(), "movs r0, #1");
+/// ```
+pub fn expand_includes<F, E>(input: &str, path: &Path, resolve: F) -> Result<String, IncludeError>
+where
+    F: FnMut(&Path) -> Result<String, E>,
+    E: Display,
+{
+    expand_includes_with_locations(input, path, resolve).map(|(output, _)| output)
+}
+
+/// The file and line a line of an expanded source comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    /// Line in the file, starting at 1
+    pub line: usize,
+}
+
+/// Same as [`expand_includes`], but also returns where each line of the output comes from,
+/// so that the lines of the errors found in it can be traced back to their file.
+pub fn expand_includes_with_locations<F, E>(
+    input: &str,
+    path: &Path,
+    mut resolve: F,
+) -> Result<(String, Vec<SourceLocation>), IncludeError>
+where
+    F: FnMut(&Path) -> Result<String, E>,
+    E: Display,
+{
+    let mut stack = vec![path.to_path_buf()];
+    let mut locations = Vec::new();
+    let output = include_into(input, &mut stack, &mut resolve, &mut locations)?;
+    Ok((output, locations))
+}
+
+/// `stack` holds the files being included, the last one being the current file
+fn include_into<F, E>(
+    input: &str,
+    stack: &mut Vec<PathBuf>,
+    resolve: &mut F,
+    locations: &mut Vec<SourceLocation>,
+) -> Result<String, IncludeError>
+where
+    F: FnMut(&Path) -> Result<String, E>,
+    E: Display,
+{
+    let mut out = String::with_capacity(input.len());
+    for (i, line) in input.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let Some(operand) = directive(line, ".include") else {
+            locations.push(SourceLocation {
+                path: stack.last().cloned().unwrap_or_default(),
+                line: i + 1,
+            });
+            out.push_str(line);
+            continue;
+        };
+
+        let name = operand
+            .strip_prefix('"')
+            .and_then(|operand| operand.strip_suffix('"'))
+            .ok_or_else(|| IncludeError::InvalidPath(operand.to_owned()))?;
+        let directory = stack.last().and_then(|file| file.parent());
+        let path = directory.unwrap_or(Path::new("")).join(name);
+        let display = path.display().to_string();
+
+        if stack.contains(&path) {
+            return Err(IncludeError::Cycle(display));
+        }
+        if stack.len() > MAX_INCLUDE_DEPTH {
+            return Err(IncludeError::TooDeep(display));
+        }
+        let contents = resolve(&path).map_err(|e| IncludeError::Unreadable {
+            path: display,
+            reason: e.to_string(),
+        })?;
+
+        stack.push(path);
+        out.push_str(&include_into(&contents, stack, resolve, locations)?);
+        stack.pop();
+    }
+    Ok(out)
+}
+
+/// A line of the source along with its original line number, starting at 1
+pub(crate) type Line = (usize, String);
+
+/// Values defined with `.equ` (alias `.set`)
+type Symbols = BTreeMap<String, u32>;
+
+/// Returns the operands of `line` if it is the given directive.
+fn directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let line = line.trim();
+    let prefix = line.get(..name.len())?;
+    let rest = &line[name.len()..];
+    if !prefix.eq_ignore_ascii_case(name) || rest.starts_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+    Some(strip_comment(rest).trim())
+}
+
+fn strip_comment(text: &str) -> &str {
+    &text[..text.find(['@', ';']).unwrap_or(text.len())]
+}
+
+/// A decimal or `0x` prefixed hexadecimal number, or a symbol
+fn evaluate(text: &str, symbols: &Symbols) -> Result<u32, PreprocessError> {
+    let invalid = || PreprocessError::InvalidValue(text.to_owned());
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16).map_err(|_| invalid())
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        text.parse().map_err(|_| invalid())
+    } else if !text.is_empty() && text.chars().all(is_label_char) {
+        symbols
+            .get(text)
+            .copied()
+            .ok_or_else(|| PreprocessError::UnknownSymbol(text.to_owned()))
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Replaces the symbols of the `#symbol` and `#(expression)` immediates by their value,
+/// leaving strings untouched.
+fn substitute(line: &str, symbols: &Symbols) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_string = false;
+    let mut after_hash = false;
+    // depth of the parentheses of the current `#(expression)`
+    let mut depth = 0;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let word = &rest[..rest.find(|c| !is_label_char(c)).unwrap_or(rest.len())];
+        if !in_string && (after_hash || depth > 0) && !word.is_empty() {
+            match symbols.get(word) {
+                Some(value) if !c.is_ascii_digit() => out.push_str(&value.to_string()),
+                _ => out.push_str(word),
+            }
+            rest = &rest[word.len()..];
+            after_hash = false;
+            continue;
+        }
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string && (after_hash || depth > 0) => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ => {}
+        }
+        after_hash = c == '#' && !in_string;
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// A `.if` block being expanded
+struct Conditional {
+    /// Line of the `.if`
+    line: usize,
+    /// Whether the enclosing block is assembled
+    parent: bool,
+    /// Whether the current branch is assembled, if the enclosing block is
+    taken: bool,
+    /// Whether the `.else` has been reached
+    otherwise: bool,
+}
+
+/// A macro defined with `.macro`
+struct Macro {
+    name: String,
+    /// Line of the `.macro`
+    line: usize,
+    params: Vec<String>,
+    body: Vec<String>,
+}
+
+impl Macro {
+    fn new(definition: &str, line: usize) -> Self {
+        let mut words = definition
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty());
+        Self {
+            name: words.next().unwrap_or_default().to_owned(),
+            line,
+            params: words.map(str::to_owned).collect(),
+            body: Vec::new(),
+        }
+    }
+
+    /// The body of the macro with `\param` replaced by the given arguments
+    fn expand(&self, args: &str) -> Result<Vec<String>, PreprocessError> {
+        let args: Vec<_> = if args.trim().is_empty() {
+            Vec::new()
+        } else {
+            args.split(',').map(str::trim).collect()
+        };
+        if args.len() != self.params.len() {
+            return Err(PreprocessError::MacroArguments {
+                name: self.name.clone(),
+                expected: self.params.len(),
+                found: args.len(),
+            });
+        }
+
+        // longer names first, so that `\ab` is not replaced as `\a` followed by `b`
+        let mut substitutions: Vec<_> = self.params.iter().zip(args).collect();
+        substitutions.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+        Ok(self
+            .body
+            .iter()
+            .map(|line| {
+                substitutions
+                    .iter()
+                    .fold(line.clone(), |line, (param, arg)| {
+                        line.replace(&format!("\\{param}"), arg)
+                    })
+            })
+            .collect())
+    }
+}
+
+/// A `.rept` block being collected
+struct Repetition {
+    /// Line of the `.rept`
+    line: usize,
+    count: u32,
+    /// Number of nested `.rept` blocks currently open in the body
+    depth: usize,
+    /// The lines of the body along with their line number
+    body: Vec<(usize, String)>,
+}
+
+/// A line waiting to be expanded
+struct Pending {
+    number: usize,
+    text: String,
+    /// The macros the line was expanded from
+    macros: Vec<String>,
+}
+
+/// Handles `.equ`, `.if` / `.else` / `.endif`, `.macro` / `.endm` and `.rept` / `.endr`.
+/// Directives and excluded lines are removed. The lines expanded from a macro have the line
+/// number of its invocation, the repeated lines keep their own.
+///
+/// # Arguments
+///
+/// * `lines`: The lines of the source.
+///
+/// returns: The expanded lines, or the error along with the line it was found on.
+pub(crate) fn expand(lines: Vec<Line>) -> Result<Vec<Line>, (usize, PreprocessError)> {
+    let mut symbols = Symbols::new();
+    let mut conditionals: Vec<Conditional> = Vec::new();
+    let mut macros: BTreeMap<String, Macro> = BTreeMap::new();
+    let mut defining: Option<Macro> = None;
+    let mut repeating: Option<Repetition> = None;
+    let mut out = Vec::with_capacity(lines.len());
+
+    let mut pending: VecDeque<_> = lines
+        .into_iter()
+        .map(|(number, text)| Pending {
+            number,
+            text,
+            macros: Vec::new(),
+        })
+        .collect();
+
+    while let Some(Pending {
+        number,
+        text: line,
+        macros: expanded_from,
+    }) = pending.pop_front()
+    {
+        let error = |error| (number, error);
+        let active = conditionals.last().is_none_or(|c| c.parent && c.taken);
+
+        if let Some(definition) = defining.as_mut() {
+            if directive(&line, ".endm").is_some() {
+                let definition = defining.take().unwrap();
+                macros.insert(definition.name.clone(), definition);
+            } else if let Some(nested) = directive(&line, ".macro") {
+                return Err(error(PreprocessError::NestedMacro(
+                    Macro::new(nested, number).name,
+                )));
+            } else {
+                definition.body.push(line);
+            }
+        } else if let Some(repetition) = repeating.as_mut() {
+            if directive(&line, ".endr").is_some() && repetition.depth == 0 {
+                let repetition = repeating.take().unwrap();
+                for _ in 0..repetition.count {
+                    for (number, text) in repetition.body.iter().rev() {
+                        pending.push_front(Pending {
+                            number: *number,
+                            text: text.clone(),
+                            macros: expanded_from.clone(),
+                        });
+                    }
+                }
+            } else {
+                if directive(&line, ".rept").is_some() {
+                    repetition.depth += 1;
+                } else if directive(&line, ".endr").is_some() {
+                    repetition.depth -= 1;
+                }
+                repetition.body.push((number, line));
+            }
+        } else if let Some(condition) = directive(&line, ".if") {
+            // the condition of an excluded block may reference anything
+            let taken = !active || evaluate(condition, &symbols).map_err(error)? != 0;
+            conditionals.push(Conditional {
+                line: number,
+                parent: active,
+                taken,
+                otherwise: false,
+            });
+        } else if directive(&line, ".else").is_some() {
+            let conditional = conditionals
+                .last_mut()
+                .ok_or(error(PreprocessError::UnmatchedDirective(".else")))?;
+            if conditional.otherwise {
+                return Err(error(PreprocessError::DuplicateElse(conditional.line)));
+            }
+            conditional.otherwise = true;
+            conditional.taken = !conditional.taken;
+        } else if directive(&line, ".endif").is_some() {
+            conditionals
+                .pop()
+                .ok_or(error(PreprocessError::UnmatchedDirective(".endif")))?;
+        } else if !active {
+            // excluded lines are dropped
+        } else if let Some(definition) = directive(&line, ".macro") {
+            defining = Some(Macro::new(definition, number));
+        } else if directive(&line, ".endm").is_some() {
+            return Err(error(PreprocessError::UnmatchedEndm));
+        } else if let Some(count) = directive(&line, ".rept") {
+            repeating = Some(Repetition {
+                line: number,
+                count: evaluate(count, &symbols).map_err(error)?,
+                depth: 0,
+                body: Vec::new(),
+            });
+        } else if directive(&line, ".endr").is_some() {
+            return Err(error(PreprocessError::UnmatchedEndr));
+        } else if let Some(definition) =
+            directive(&line, ".equ").or_else(|| directive(&line, ".set"))
+        {
+            let (name, value) = definition
+                .split_once(',')
+                .ok_or(error(PreprocessError::InvalidValue(definition.to_owned())))?;
+            let value = evaluate(value.trim(), &symbols).map_err(error)?;
+            symbols.insert(name.trim().to_owned(), value);
+        } else if let Some(invoked) = invocation(&line, &macros) {
+            let (name, args) = invoked;
+            if expanded_from.iter().any(|m| m == name) {
+                return Err(error(PreprocessError::RecursiveMacro(name.to_owned())));
+            }
+            let body = macros[name].expand(args).map_err(error)?;
+            let mut chain = expanded_from.clone();
+            chain.push(name.to_owned());
+            for text in body.into_iter().rev() {
+                pending.push_front(Pending {
+                    number,
+                    text,
+                    macros: chain.clone(),
+                });
+            }
+        } else {
+            out.push((number, substitute(&line, &symbols)));
+        }
+    }
+
+    if let Some(definition) = defining {
+        return Err((definition.line, PreprocessError::UnterminatedMacro));
+    }
+    if let Some(repetition) = repeating {
+        return Err((repetition.line, PreprocessError::UnterminatedRept));
+    }
+    match conditionals.first() {
+        Some(conditional) => Err((conditional.line, PreprocessError::UnterminatedIf)),
+        None => Ok(out),
+    }
+}
+
+/// Returns the name of the invoked macro and its arguments, if `line` invokes one.
+fn invocation<'a>(line: &'a str, macros: &BTreeMap<String, Macro>) -> Option<(&'a str, &'a str)> {
+    let line = line.trim_start();
+    let end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let (name, args) = line.split_at(end);
+    macros
+        .contains_key(name)
+        .then_some((name, strip_comment(args)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &str) -> Vec<Line> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.to_owned()))
+            .collect()
+    }
+
+    fn kept(input: &str) -> Vec<String> {
+        expand(lines(input))
+            .unwrap()
+            .into_iter()
+            .map(|(_, line)| line.trim().to_owned())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn nested_conditionals() {
+        let input = "
+            .equ A, 1
+            .if A
+            a
+            .if 0
+            b
+            .else
+            c
+            .endif
+            .else
+            d
+            .if UNDEFINED
+            e
+            .endif
+            .endif";
+        assert_eq!(kept(input), ["a", "c"]);
+    }
+
+    #[test]
+    fn symbol_substitution() {
+        let input = ".set VALUE, 0x10\nmovs r0, #VALUE\n.asciz \"#VALUE\"\nmovs r1, #(VALUE * (VALUE + 0x1))";
+        assert_eq!(
+            kept(input),
+            [
+                "movs r0, #16",
+                ".asciz \"#VALUE\"",
+                "movs r1, #(16 * (16 + 0x1))"
+            ]
+        );
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert_eq!(
+            expand(lines(".if 1\n.if 0\n.endif")),
+            Err((1, PreprocessError::UnterminatedIf))
+        );
+        assert_eq!(
+            expand(lines("movs r0, #1\n.endif")),
+            Err((2, PreprocessError::UnmatchedDirective(".endif")))
+        );
+        assert_eq!(
+            expand(lines(".if 1\na\n.else\nb\n.else\nc\n.endif")),
+            Err((5, PreprocessError::DuplicateElse(1)))
+        );
+        assert_eq!(
+            expand(lines(".if DEBUG\n.endif")),
+            Err((1, PreprocessError::UnknownSymbol("DEBUG".to_owned())))
+        );
+    }
+
+    #[test]
+    fn macro_expansion() {
+        let input = r"
+            .macro twice reg, value
+            movs \reg, #\value
+            adds \reg, \reg, \reg
+            .endm
+            twice r0, 1
+            twice r1, 2 @ comment";
+        assert_eq!(
+            kept(input),
+            [
+                "movs r0, #1",
+                "adds r0, r0, r0",
+                "movs r1, #2",
+                "adds r1, r1, r1"
+            ]
+        );
+    }
+
+    #[test]
+    fn macro_line_numbers() {
+        let input = ".macro one\nmovs r0, #1\n.endm\none";
+        assert_eq!(
+            expand(lines(input)),
+            Ok(vec![(4, "movs r0, #1".to_owned())])
+        );
+    }
+
+    #[test]
+    fn macro_errors() {
+        assert_eq!(
+            expand(lines(".macro loop\nloop\n.endm\nloop")),
+            Err((4, PreprocessError::RecursiveMacro("loop".to_owned())))
+        );
+        assert_eq!(
+            expand(lines(".macro one a\n.endm\none")),
+            Err((
+                3,
+                PreprocessError::MacroArguments {
+                    name: "one".to_owned(),
+                    expected: 1,
+                    found: 0
+                }
+            ))
+        );
+        assert_eq!(
+            expand(lines("movs r0, #1\n.macro one")),
+            Err((2, PreprocessError::UnterminatedMacro))
+        );
+    }
+
+    #[test]
+    fn repetition() {
+        let input = "
+            .equ COUNT, 2
+            .rept COUNT
+            a
+            .rept 2
+            b
+            .endr
+            .endr";
+        assert_eq!(kept(input), ["a", "b", "b", "a", "b", "b"]);
+        let numbers: Vec<_> = expand(lines(input))
+            .unwrap()
+            .into_iter()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(number, _)| number)
+            .collect();
+        assert_eq!(numbers, [4, 6, 6, 4, 6, 6]);
+        assert_eq!(
+            expand(lines(".rept 3\nmovs r0, #1")),
+            Err((1, PreprocessError::UnterminatedRept))
+        );
+        assert_eq!(
+            expand(lines(".endr")),
+            Err((1, PreprocessError::UnmatchedEndr))
+        );
+    }
+
+    fn files(path: &Path) -> Result<String, &'static str> {
+        match path.to_str().unwrap() {
+            "src/lib/a.s" => Ok(".include \"b.s\"\nmovs r1, #2".to_owned()),
+            "src/lib/b.s" => Ok("movs r0, #1".to_owned()),
+            "src/loop.s" => Ok(".include \"loop.s\"".to_owned()),
+            _ => Err("not found"),
+        }
+    }
+
+    #[test]
+    fn include_chain() {
+        let output = expand_includes(
+            ".include \"lib/a.s\" @ helpers\nmovs r2, #3",
+            Path::new("src/main.s"),
+            files,
+        );
+        assert_eq!(output.unwrap(), "movs r0, #1\nmovs r1, #2\nmovs r2, #3");
+    }
+
+    #[test]
+    fn include_locations() {
+        let (_, locations) = expand_includes_with_locations(
+            ".include \"lib/a.s\"\nmovs r2, #3",
+            Path::new("src/main.s"),
+            files,
+        )
+        .unwrap();
+
+        let found: Vec<_> = locations
+            .iter()
+            .map(|l| (l.path.to_str().unwrap(), l.line))
+            .collect();
+        assert_eq!(
+            found,
+            [("src/lib/b.s", 1), ("src/lib/a.s", 2), ("src/main.s", 2)]
+        );
+    }
+
+    #[test]
+    fn include_errors() {
+        let main = Path::new("src/main.s");
+        assert_eq!(
+            expand_includes(".include \"loop.s\"", main, files),
+            Err(IncludeError::Cycle("src/loop.s".to_owned()))
+        );
+        assert_eq!(
+            expand_includes(".include \"missing.s\"", main, files),
+            Err(IncludeError::Unreadable {
+                path: "src/missing.s".to_owned(),
+                reason: "not found".to_owned()
+            })
+        );
+        assert_eq!(
+            expand_includes(".include missing.s", main, files),
+            Err(IncludeError::InvalidPath("missing.s".to_owned()))
+        );
+    }
+}
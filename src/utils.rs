@@ -28,8 +28,42 @@ macro_rules! impl_make_appliable {
 
 impl_make_appliable!(A B C D E F G H I J K L M);
 
+/// Handles `\n`, `\\` and `\uXXXX` escapes, other sequences are left untouched.
 pub fn unescape_string(input: &str) -> String {
-    input.replace("\\n", "\n").replace("\\\\", "\\")
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        let rest = chars.as_str();
+        match rest.chars().next() {
+            Some('n') => {
+                output.push('\n');
+                chars.next();
+            }
+            Some('\\') => {
+                output.push('\\');
+                chars.next();
+            }
+            Some('u') => match rest
+                .get(1..5)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32)
+            {
+                Some(unicode) => {
+                    output.push(unicode);
+                    chars = rest[5..].chars();
+                }
+                None => output.push(c),
+            },
+            _ => output.push(c),
+        }
+    }
+    output
 }
 
 #[cfg(test)]
@@ -42,4 +76,12 @@ mod tests {
         let fun = raw_fun.make_appliable();
         assert_eq!(fun((1, 2, 3)), raw_fun(1, 2, 3));
     }
+
+    #[test]
+    fn unescape() {
+        assert_eq!(unescape_string(r"a\nb\\c"), "a\nb\\c");
+        assert_eq!(unescape_string(r"caf\u00e9"), "café");
+        assert_eq!(unescape_string(r"\u00"), r"\u00");
+        assert_eq!(unescape_string(r"\t"), r"\t");
+    }
 }
@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
     use parm_assembler::{export_to_logisim, LogisimProgram};
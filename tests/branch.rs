@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
     use parm_assembler::LogisimProgram;
@@ -31,4 +33,26 @@ mod tests {
 
         assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
     }
+
+    #[test]
+    fn numeric_offset() {
+        let input = "
+        b #2
+        beq #-3
+        b #-1";
+
+        let output = parm_assembler::export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\ne002 d0fd e7ff";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn hex_offset() {
+        let hex = parm_assembler::export_to_logisim("b #0x4\nbeq #-0x3").unwrap();
+        let decimal = parm_assembler::export_to_logisim("b #4\nbeq #-3").unwrap();
+
+        assert_eq!(hex, decimal);
+    }
 }
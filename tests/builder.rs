@@ -0,0 +1,45 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, ExportError, ProgramBuilder, Reg};
+
+    #[test]
+    fn string() {
+        let program = ProgramBuilder::new()
+            .movs(Reg::R0, 1)
+            .label(".msg")
+            .string("Hi")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            program,
+            export_to_logisim("movs r0, #1\n.msg:\n.asciz \"Hi\"").unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_immediate() {
+        let err = ProgramBuilder::new()
+            .movs(Reg::R0, 300)
+            .build()
+            .unwrap_err();
+
+        assert!(matches!(err, ExportError::ImmediateError(_)));
+        assert_eq!(
+            err.to_string(),
+            "Invalid operand: Immediate value 300 is too large"
+        );
+    }
+
+    #[test]
+    fn unknown_label() {
+        let err = ProgramBuilder::new()
+            .branch(".nowhere")
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err.label(), Some(".nowhere"));
+    }
+}
@@ -0,0 +1,409 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::{Command, Output, Stdio};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Writes `contents` to a fresh file in the temporary directory
+    fn fixture(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("parm_assembler_cli_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn command(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_parm_assembler"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    fn run(args: &[&str]) -> String {
+        String::from_utf8(command(args).stdout).unwrap()
+    }
+
+    #[test]
+    fn symbols() {
+        let path = fixture(
+            "symbols.s",
+            r#"
+            movs r0, #1
+            .loop:
+            adds r0, #1
+            b .loop
+            .end:
+            ldr r1, .msg
+            .msg:
+            .asciz "Hi"
+            .other:
+            .asciz "!"
+            "#,
+        );
+
+        let output = run(&["symbols", path.to_str().unwrap()]);
+
+        let expected = "\
+00000001 rom .loop
+00000003 rom .end
+00000000 ram .msg
+00000002 ram .other
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn missing_input() {
+        let missing = std::env::temp_dir().join("parm_assembler_cli_missing.s");
+
+        for subcommand in ["symbols", "strings"] {
+            let output = command(&[subcommand, missing.to_str().unwrap()]);
+
+            assert!(!output.status.success(), "{subcommand}");
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert!(stderr.contains("Could not read"), "{stderr}");
+            assert!(!stderr.contains("panicked"), "{stderr}");
+        }
+    }
+
+    #[test]
+    fn failing_subcommands() {
+        let invalid = fixture("invalid.s", "movs r0, #1\nfoo r1\n");
+
+        for subcommand in ["symbols", "strings"] {
+            let output = command(&[subcommand, invalid.to_str().unwrap()]);
+            assert!(!output.status.success(), "{subcommand}");
+        }
+        assert!(!command(&["print", "foo r1"]).status.success());
+        assert!(command(&["print", "movs r0, #1"]).status.success());
+    }
+
+    #[test]
+    fn strings() {
+        let path = fixture(
+            "strings.s",
+            r#"
+            ldr r0, .hello
+            .hello:
+            .asciz "Hello world\n"
+            .asciz "!"
+            .section .rodata
+            .bye:
+            .string "Goodbye!"
+            "#,
+        );
+
+        let output = run(&["strings", path.to_str().unwrap()]);
+
+        let expected = "\
+00000000 .hello \"Hello world\\n\"
+0000000c - \"!\"
+0000000d .bye \"Goodbye!\"
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn dump_ast() {
+        let path = fixture(
+            "ast.s",
+            "movs r0, #1\n.loop:\nadds r0, #1 @ comment\n\nb .loop\n",
+        );
+
+        let output = run(&["assemble", "--dump-ast", path.to_str().unwrap()]);
+
+        // pretty printed entries start with their variant, indented once
+        let entries: Vec<_> = output
+            .lines()
+            .filter(|line| {
+                line.strip_prefix("    ")
+                    .is_some_and(|entry| entry.starts_with(char::is_uppercase))
+            })
+            .collect();
+        assert_eq!(entries.len(), 4, "{output}");
+        assert!(entries[1].contains("Label("), "{output}");
+        assert!(output.contains("Processed"), "{output}");
+
+        let plain = run(&["assemble", path.to_str().unwrap()]);
+        assert!(!plain.contains("Label("), "{plain}");
+    }
+
+    #[test]
+    fn multi_statement() {
+        let path = fixture("multi_statement.s", "movs r0, #1; movs r1, #2\n");
+        let rom = path.with_extension("rom");
+
+        run(&["assemble", path.to_str().unwrap()]);
+        assert_eq!(fs::read_to_string(&rom).unwrap(), "v2.0 raw\n2001");
+
+        run(&["assemble", "--multi-statement", path.to_str().unwrap()]);
+        assert_eq!(fs::read_to_string(&rom).unwrap(), "v2.0 raw\n2001 2102");
+    }
+
+    #[test]
+    fn check() {
+        let valid = fixture("check_valid.s", "movs r0, #1\n");
+        let broken = fixture("check_broken.s", "movs r0, #1\nb .missing\n");
+
+        let output = command(&["check", valid.to_str().unwrap()]);
+        assert!(output.status.success());
+        assert!(!valid.with_extension("rom").exists());
+
+        let output = command(&["check", broken.to_str().unwrap()]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Label .missing not found"), "{stderr}");
+        assert!(!broken.with_extension("rom").exists());
+    }
+
+    #[test]
+    fn fill() {
+        let path = fixture("fill.s", "movs r0, #1\n.org 3\nmovs r1, #2\n");
+        let rom = path.with_extension("rom");
+
+        run(&["assemble", path.to_str().unwrap()]);
+        assert_eq!(
+            fs::read_to_string(&rom).unwrap(),
+            "v2.0 raw\n2001 0000 0000 2102"
+        );
+
+        run(&["assemble", "--fill", "bf00", path.to_str().unwrap()]);
+        assert_eq!(
+            fs::read_to_string(&rom).unwrap(),
+            "v2.0 raw\n2001 bf00 bf00 2102"
+        );
+    }
+
+    #[test]
+    fn target() {
+        let path = fixture("target.s", "movs r0, #1\nmovs r1, #2\n");
+        let rom = path.with_extension("rom");
+
+        run(&[
+            "assemble",
+            "--target",
+            "parm-default",
+            path.to_str().unwrap(),
+        ]);
+        assert_eq!(fs::read_to_string(&rom).unwrap(), "v2.0 raw\n2001 2102");
+
+        run(&[
+            "assemble",
+            "--target",
+            "logisim-16le",
+            path.to_str().unwrap(),
+        ]);
+        assert_eq!(fs::read_to_string(&rom).unwrap(), "v2.0 raw\n0120 0221");
+
+        run(&[
+            "assemble",
+            "--target",
+            "logisim-addressed",
+            path.to_str().unwrap(),
+        ]);
+        assert_eq!(
+            fs::read_to_string(&rom).unwrap(),
+            "v3.0 hex words addressed\n0000: 2001\n0001: 2102"
+        );
+
+        assert!(
+            !command(&["assemble", "--target", "unknown", path.to_str().unwrap()])
+                .status
+                .success()
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let path = fixture(
+            "stats.s",
+            r#"
+            .loop:
+            ldr r0, .msg
+            b .loop
+            .msg:
+            .asciz "Hi"
+            "#,
+        );
+
+        let output = run(&["assemble", "-v", "--rom-size", "8", path.to_str().unwrap()]);
+
+        let expected = format!(
+            "Processed {}\n  Instructions: 2\n  ROM words: 2\n  RAM bytes: 2\n  Labels: 2\n",
+            path.display()
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn diff() {
+        let path = fixture("diff.s", "movs r0, #1\nmovs r1, #2\nmovs r2, #3\n");
+        let same = fixture("diff_same.rom", "v2.0 raw\n2001 2102 2203");
+        let changed = fixture("diff_changed.rom", "v2.0 raw\n2001 2105 2203");
+
+        let output = command(&["diff", path.to_str().unwrap(), same.to_str().unwrap()]);
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+
+        let output = command(&["diff", path.to_str().unwrap(), changed.to_str().unwrap()]);
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "0001: expected 2105, got 2102\n"
+        );
+
+        let missing = path.with_file_name("diff_missing.rom");
+        let output = command(&["diff", path.to_str().unwrap(), missing.to_str().unwrap()]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("Failed to read "), "{stderr}");
+        assert!(output.stdout.is_empty());
+    }
+
+    #[test]
+    fn include() {
+        fixture("include/lib/b.s", "movs r0, #1\n");
+        fixture("include/lib/a.s", ".include \"b.s\"\nmovs r1, #2\n");
+        let path = fixture("include/main.s", ".include \"lib/a.s\"\nmovs r2, #3\n");
+
+        run(&["assemble", path.to_str().unwrap()]);
+        assert_eq!(
+            fs::read_to_string(path.with_extension("rom")).unwrap(),
+            "v2.0 raw\n2001 2102 2203"
+        );
+    }
+
+    #[test]
+    fn error_after_include() {
+        fixture("include_error/lib.s", "movs r0, #1\nmovs r1, #2\n");
+        let path = fixture(
+            "include_error/main.s",
+            ".include \"lib.s\"\nmovs r2, #3\nadds r0, r9\n",
+        );
+
+        let output = command(&["check", path.to_str().unwrap()]);
+
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("main.s: "), "{stderr}");
+        assert!(stderr.contains("on line 3:"), "{stderr}");
+        assert!(stderr.contains("3 | adds r0, r9\n"), "{stderr}");
+    }
+
+    /// Waits until `path` holds `expected`, giving up after a few seconds
+    fn wait_for(path: &PathBuf, expected: &str) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(10) {
+            if fs::read_to_string(path).is_ok_and(|contents| contents == expected) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    #[test]
+    fn watch() {
+        let path = fixture("watch.s", "movs r0, #1\n");
+        let rom = path.with_extension("rom");
+        let _ = fs::remove_file(&rom);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_parm_assembler"))
+            .args(["assemble", "--watch", path.to_str().unwrap()])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let initial = wait_for(&rom, "v2.0 raw\n2001");
+        fs::write(&path, "movs r0, #2\n").unwrap();
+        let changed = wait_for(&rom, "v2.0 raw\n2002");
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(initial, "the initial assemble did not happen");
+        assert!(changed, "the change was not assembled");
+    }
+
+    #[test]
+    fn watch_include() {
+        let lib = fixture("watch_include/lib.s", "movs r0, #1\n");
+        let path = fixture("watch_include/main.s", ".include \"lib.s\"\n");
+        let rom = path.with_extension("rom");
+        let _ = fs::remove_file(&rom);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_parm_assembler"))
+            .args(["assemble", "--watch", path.to_str().unwrap()])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let initial = wait_for(&rom, "v2.0 raw\n2001");
+        fs::write(&lib, "movs r0, #2\n").unwrap();
+        let changed = wait_for(&rom, "v2.0 raw\n2002");
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(initial, "the initial assemble did not happen");
+        assert!(changed, "the change of the included file was not assembled");
+    }
+
+    #[test]
+    fn unwritable_output() {
+        let path = fixture("unwritable.s", "movs r0, #1\n");
+        // a directory cannot be written as a file
+        fs::create_dir_all(path.with_extension("rom")).unwrap();
+
+        let output = command(&["assemble", path.to_str().unwrap()]);
+
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("could not write the output"), "{stderr}");
+        assert!(!stderr.contains("panicked"), "{stderr}");
+    }
+
+    #[test]
+    fn werror() {
+        let path = fixture("werror.s", "lsls r0, r1, #0\n");
+
+        for subcommand in ["assemble", "check"] {
+            let output = command(&[subcommand, path.to_str().unwrap()]);
+            assert!(output.status.success(), "{subcommand}");
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert!(
+                stderr.contains("warning: instruction 0: shift by zero is a move"),
+                "{stderr}"
+            );
+
+            let output = command(&[subcommand, "--werror", path.to_str().unwrap()]);
+            assert!(!output.status.success(), "{subcommand}");
+        }
+
+        let clean = fixture("werror_clean.s", "movs r0, #1\n");
+        let output = command(&["check", "--werror", clean.to_str().unwrap()]);
+        assert!(output.status.success());
+    }
+
+    /// The tests capture the output, which is not a terminal
+    #[test]
+    fn no_color_when_piped() {
+        let path = fixture("no_color.s", "movs r0, #1\n  lsls r0, r8, #1\n");
+
+        let output = command(&["check", path.to_str().unwrap()]);
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(!stderr.contains('\x1b'), "{stderr:?}");
+        let expected = "  |
+2 |   lsls r0, r8, #1
+  |   ^^^^^^^^^^^^^^^
+";
+        assert!(stderr.starts_with("error: "), "{stderr}");
+        assert!(stderr.ends_with(expected), "{stderr}");
+        assert!(output.stdout.is_empty());
+    }
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, LogisimProgram};
+
+    fn program(debug: u32) -> String {
+        format!(
+            "
+            .equ DEBUG, {debug}
+            movs r0, #1
+            .if DEBUG
+            movs r1, #2
+            .endif
+            movs r2, #3"
+        )
+    }
+
+    #[test]
+    fn excluded() {
+        let output = export_to_logisim(&program(0)).unwrap();
+
+        let expected = "v2.0 raw\n2001 2203";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn included() {
+        let output = export_to_logisim(&program(1)).unwrap();
+
+        let expected = "v2.0 raw\n2001 2102 2203";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn else_branch() {
+        let input = "
+            .equ DEBUG, 0
+            .if DEBUG
+            movs r0, #1
+            .else
+            movs r0, #2
+            .endif";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n2002";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn unterminated() {
+        let err = export_to_logisim("movs r0, #1\n.if 1\nmovs r1, #2").unwrap_err();
+
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to expand directive on line 2: .if without a matching .endif"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! The encoding API, which is also available without the `std` feature.
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use bitvec::field::BitField;
+    use parm_assembler::{Args, FullInstr, Immediate3, Immediate8, Instr, Reg, ToBinary};
+
+    #[test]
+    fn encode() {
+        let instr = FullInstr {
+            instr: Instr::Movs,
+            args: Args::RdImm8(Reg::R1, Immediate8::new(42).unwrap()),
+        };
+        assert_eq!(instr.to_binary().load_be::<u16>(), 0x212a);
+
+        let instr = FullInstr {
+            instr: Instr::Adds2,
+            args: Args::RdRnImm3(Reg::R0, Reg::R1, Immediate3::new(2).unwrap()),
+        };
+        assert_eq!(instr.to_binary().load_be::<u16>(), 0x1c88);
+    }
+
+    #[test]
+    fn unresolved_operands() {
+        for (instr, args) in [
+            (Instr::B, Args::Label("loop".to_owned())),
+            (Instr::Ldr3, Args::RtLabel(Reg::R0, "msg".to_owned())),
+            (Instr::Ldr3, Args::RtConstant(Reg::R0, 1000)),
+        ] {
+            let instr = FullInstr { instr, args };
+            assert_eq!(instr.encode_u16(), None, "{instr}");
+            assert!(instr.args.fields().is_empty());
+        }
+    }
+
+    #[test]
+    fn high_registers() {
+        for args in [
+            Args::RdImm8(Reg::SP, Immediate8::new(1).unwrap()),
+            Args::RdRnImm3(Reg::R0, Reg::PC, Immediate3::new(1).unwrap()),
+            Args::RdRnRm(Reg::R0, Reg::R1, Reg::SP),
+        ] {
+            let instr = FullInstr {
+                instr: Instr::Movs,
+                args,
+            };
+            assert_eq!(instr.encode_u16(), None, "{instr}");
+        }
+    }
+
+    #[test]
+    fn builds_without_std() {
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+        let status = Command::new(cargo)
+            .args(["build", "--lib", "--no-default-features"])
+            .env("CARGO_TARGET_DIR", env!("CARGO_TARGET_TMPDIR"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::export_to_logisim;
+
+    const INPUT: &str = r#"
+	.text
+	.globl	run
+	.p2align	1
+	.type	run,%function
+run:                                    @ @run
+	movs	r0, #3
+	ldr	r1, .LCPI0_0
+.LBB0_1:                                @ =>This Inner Loop Header: Depth=1
+	subs	r0, #1
+	bne	.LBB0_1
+	b	.LBB0_1
+.LCPI0_0:
+	.long	.L.str
+	.type	.L.str,%object                  @ @.str
+	.section	.rodata.str1.1,"aMS",%progbits,1
+.L.str:
+	.asciz	"Hello"
+"#;
+
+    #[test]
+    fn crlf() {
+        let output = export_to_logisim(&INPUT.replace('\n', "\r\n")).unwrap();
+
+        assert_eq!(output, export_to_logisim(INPUT).unwrap());
+        assert_eq!(output.rom, "v2.0 raw\n2003 2100 3801 d1fc e7fb");
+    }
+}
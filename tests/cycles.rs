@@ -0,0 +1,24 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::estimate_cycles;
+
+    #[test]
+    fn loop_body() {
+        let input = "
+        .loop:
+        ldr r1, [sp, #4]
+        adds r1, r1, r2
+        str r1, [sp, #4]
+        subs r0, #1
+        bne .loop";
+
+        assert_eq!(estimate_cycles(input).unwrap(), 2 + 1 + 2 + 1 + 3);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(estimate_cycles("@ nothing to run").unwrap(), 0);
+    }
+}
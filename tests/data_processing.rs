@@ -1,6 +1,8 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
-    use parm_assembler::{export_to_logisim, LogisimProgram};
+    use parm_assembler::{export_to_logisim, ExportError, LogisimProgram};
 
     #[test]
     fn one() {
@@ -133,4 +135,99 @@ mod tests {
 
         assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
     }
+
+    #[test]
+    fn flag_only() {
+        let input = "
+        cmp r0, r1
+        cmp r0, #5
+        cmn r2, r3
+        tst r4, r5";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n4288 2805 42da 422c";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn compare_byte_bounds() {
+        let output = export_to_logisim("cmp r0, #255").unwrap();
+        assert_eq!(
+            output,
+            LogisimProgram::with_rom("v2.0 raw\n28ff".to_owned())
+        );
+
+        let output = export_to_logisim("cmp r0, #0").unwrap();
+        assert_eq!(
+            output,
+            LogisimProgram::with_rom("v2.0 raw\n2800".to_owned())
+        );
+
+        assert!(matches!(
+            export_to_logisim("cmp r0, #256"),
+            Err(ExportError::ParseError(_))
+        ));
+    }
+
+    /// Every data processing instruction with rdn = r2 and rm = r5.
+    /// The reference encodings are `0100 00 opcode rm rdn`, see section A6.2.2 of the ARMv6-M ARM.
+    #[test]
+    fn every_opcode() {
+        let cases = [
+            ("ands r2, r5", "402a"),
+            ("eors r2, r5", "406a"),
+            ("lsls r2, r5", "40aa"),
+            ("lsrs r2, r5", "40ea"),
+            ("asrs r2, r5", "412a"),
+            ("adcs r2, r5", "416a"),
+            ("sbcs r2, r5", "41aa"),
+            ("rors r2, r5", "41ea"),
+            ("tst r2, r5", "422a"),
+            ("rsbs r2, r5, #0", "426a"),
+            ("cmp r2, r5", "42aa"),
+            ("cmn r2, r5", "42ea"),
+            ("orrs r2, r5", "432a"),
+            ("muls r2, r5, r2", "436a"),
+            ("bics r2, r5", "43aa"),
+            ("mvns r2, r5", "43ea"),
+        ];
+
+        for (input, expected) in cases {
+            let output = export_to_logisim(input).unwrap();
+            assert_eq!(
+                output,
+                LogisimProgram::with_rom(format!("v2.0 raw\n{expected}")),
+                "{input}"
+            );
+        }
+    }
+
+    /// The immediate is loaded into r6, which is clobbered
+    #[test]
+    fn logical_immediate() {
+        let input = "
+        ands r0, #0x0F
+        orrs r1, #128 @ set the top bit
+        eors r2, #1";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n260f 4030 2680 4331 2601 4072";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+        assert!(export_to_logisim("ands r6, #1").is_err());
+    }
+
+    #[test]
+    fn logical_immediate_in_comment() {
+        for input in [
+            "movs r1, #2 @ then ands r0, #1",
+            "movs r1, #2 ; then orrs r0, #1",
+        ] {
+            let output = export_to_logisim(input).unwrap();
+            assert_eq!(output.rom, "v2.0 raw\n2102", "{input}");
+        }
+    }
 }
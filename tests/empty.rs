@@ -0,0 +1,37 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, LogisimProgram};
+
+    fn empty() -> LogisimProgram {
+        LogisimProgram::with_rom("v2.0 raw".to_owned())
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(export_to_logisim("").unwrap(), empty());
+        assert_eq!(export_to_logisim("\n\n  \n").unwrap(), empty());
+    }
+
+    #[test]
+    fn comments_only() {
+        let input = "
+        @ nothing to see here
+        ; nor there
+        ";
+
+        assert_eq!(export_to_logisim(input).unwrap(), empty());
+    }
+
+    #[test]
+    fn directives_only() {
+        let input = "
+        .equ LENGTH, 4
+        .if LENGTH
+        .endif
+        .text";
+
+        assert_eq!(export_to_logisim(input).unwrap(), empty());
+    }
+}
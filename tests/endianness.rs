@@ -0,0 +1,38 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        export_to_logisim, export_to_logisim_with_options, ConvertOptions, Endianness,
+        ExportOptions,
+    };
+
+    const INPUT: &str = r#"
+        ldr r0, .str
+        .str:
+        .asciz "Hi"
+    "#;
+
+    #[test]
+    fn big_endian_by_default() {
+        let output = export_to_logisim(INPUT).unwrap();
+
+        assert_eq!(output.ram, "v2.0 raw\n0048 0069");
+    }
+
+    #[test]
+    fn little_endian_ram() {
+        let options = ExportOptions {
+            ram: ConvertOptions {
+                endianness: Endianness::Little,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let output = export_to_logisim_with_options(INPUT, &options).unwrap();
+
+        assert_eq!(output.rom, export_to_logisim(INPUT).unwrap().rom);
+        assert_eq!(output.ram, "v2.0 raw\n4800 6900");
+    }
+}
@@ -0,0 +1,362 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        export_to_logisim, make_program, Args, CompleteError, ExportError, FullInstr, Immediate5,
+        ImmediateError, Instr, ParseError, ParsedLine, Reg, RegError,
+    };
+
+    #[test]
+    fn label_not_found() {
+        let err = export_to_logisim("movs r0, #1\nb missing").unwrap_err();
+
+        match &err {
+            ExportError::CompleteError {
+                error: CompleteError::LabelNotFound { label, instruction },
+                ..
+            } => {
+                assert_eq!((label.as_str(), *instruction), ("missing", Some(1)))
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert_eq!(err.label(), Some("missing"));
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(
+            err.to_string(),
+            "Could not complete instruction: Label missing not found, used by instruction index 1"
+        );
+    }
+
+    #[test]
+    fn jump_too_far() {
+        let input = format!("beq far\n{}far:", "movs r0, r0\n".repeat(300));
+        let err = export_to_logisim(&input).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::CompleteError {
+                error: CompleteError::JumpTooFar { .. },
+                ..
+            }
+        ));
+        assert_eq!(err.label(), Some("far"));
+    }
+
+    #[test]
+    fn jump_too_far_instruction() {
+        let input = format!("far:\nbeq far\n{}beq far", "movs r0, r0\n".repeat(300));
+        let err = export_to_logisim(&input).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::CompleteError {
+                error: CompleteError::JumpTooFar {
+                    instruction: Some(301),
+                    ..
+                },
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not complete instruction: Label far is too far away from instruction index 301: -304"
+        );
+        assert_eq!(err.line(), Some(303));
+
+        // the instructions a line expands into do not shift the lines after it
+        let input = format!(
+            "ldr r1, =1000\nfar:\n{}beq far",
+            "movs r0, r0\n".repeat(300)
+        );
+        assert_eq!(export_to_logisim(&input).unwrap_err().line(), Some(303));
+    }
+
+    #[test]
+    fn parse_error_line() {
+        let input = "movs r0, #1\n\n  movs r1, #2\nfoo r0\nmovs r2, #3";
+        let err = export_to_logisim(input).unwrap_err();
+
+        assert!(matches!(err, ExportError::ParseError(_)));
+        assert_eq!(err.line(), Some(4));
+        assert_eq!(err.label(), None);
+    }
+
+    #[test]
+    fn muls_destination() {
+        let err = export_to_logisim("muls r0, r1, r2").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::CompleteError {
+                error: CompleteError::MulsDestination {
+                    rd: Reg::R0,
+                    rn: Reg::R1,
+                    rm: Reg::R2
+                },
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not complete instruction: Destination r0 of muls must also be one of its sources, found r1 and r2"
+        );
+    }
+
+    #[test]
+    fn invalid_register() {
+        let err = export_to_logisim("movs r0, #1\nlsls r0, r8, #1").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::InvalidRegister {
+                line: 2,
+                error: RegError::OutOfRange(8)
+            })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 2: register r8 is not available (only r0-r7, sp, pc)"
+        );
+    }
+
+    #[test]
+    fn high_register() {
+        let err = export_to_logisim("adds r0, sp, r1").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::InvalidRegister {
+                line: 1,
+                error: RegError::HighRegister(Reg::SP)
+            })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 1: register sp cannot be used here (only r0-r7)"
+        );
+    }
+
+    #[test]
+    fn imm3_too_large() {
+        let err = export_to_logisim("movs r1, #1\nadds r0, r1, #8").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::Imm3TooLarge {
+                line: 2,
+                rd: Reg::R0,
+                value: 8,
+                ..
+            })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 2: immediate #8 is too large for the 3 bits form of adds, did you mean `adds r0, #8` (8 bits immediate, with rd == rn)?"
+        );
+
+        let err = export_to_logisim("SUBS r2, r2, #200").unwrap_err();
+        assert!(
+            err.to_string().contains("did you mean `subs r2, #200`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn dollar_immediate() {
+        let err = export_to_logisim("movs r0, $5").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::DollarImmediate { line: 1, .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 1: `$5` is not an immediate, use `#` for ARM immediates: `#5`"
+        );
+
+        let err = export_to_logisim("ldr r0, [r1, $4]").unwrap_err();
+        assert!(err.to_string().contains("`#4`"), "{err}");
+    }
+
+    #[test]
+    fn arm_mode() {
+        assert!(export_to_logisim(".code 16\nmovs r0, #1").is_ok());
+
+        let err = export_to_logisim("movs r0, #1\n.code 32\nmovs r1, #1").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::ArmMode { line: 2 })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 2: ARM (32-bit) mode is not supported; this assembler targets Thumb only"
+        );
+    }
+
+    #[test]
+    fn negative_immediate() {
+        assert!(export_to_logisim("movs r0, #255").is_ok());
+
+        let err = export_to_logisim("movs r1, #1\nmovs r0, #-1").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::NegativeImmediate {
+                line: 2,
+                rd: Some(Reg::R0),
+                value: 1,
+                ..
+            })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 2: movs takes an unsigned immediate, #-1 cannot be encoded, use `movs r0, #1` then `rsbs r0, r0, #0`, or `movs r0, #0` then `mvns r0, r0`"
+        );
+
+        let err = export_to_logisim("adds\tr2, #-4").unwrap_err();
+        assert!(err.to_string().ends_with("use `subs` with #4"), "{err}");
+
+        let err = export_to_logisim("ldr r0, [r1, #-4]").unwrap_err();
+        assert!(err.to_string().ends_with("#-4 cannot be encoded"), "{err}");
+    }
+
+    #[test]
+    fn trailing_text() {
+        let err = export_to_logisim("movs r1, #1\nmovs r0, #5 xyz\nmovs r2, #2").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::TrailingText { line: 2, .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 2: unexpected `xyz` after the instruction"
+        );
+
+        assert!(export_to_logisim("movs r0, #5 @ xyz\nmovs r1, #1 ; xyz\nmovs r2, #2   ").is_ok());
+    }
+
+    #[test]
+    fn non_ascii_char() {
+        let err = export_to_logisim("movs r0, #'é'").unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::ParseError(ParseError::NonAsciiChar {
+                line: 1,
+                literal: 'é'
+            })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Could not parse input: Failed to parse assembly code on line 1: 'é' is not an ASCII character, it has no single byte code"
+        );
+    }
+
+    #[test]
+    fn invalid_number() {
+        for (input, number, message) in [
+            ("movs r0, #0x", "0x", "invalid hex digit in `0x`"),
+            ("movs r0, #0xG", "0xG", "invalid hex digit in `0xG`"),
+            ("movs r0, #0b2", "0b2", "invalid binary digit in `0b2`"),
+            (
+                "movs r0, #08",
+                "08",
+                "`08` starts with a zero, which other assemblers read as octal, \
+                 write it without the zero or with a `0x` prefix",
+            ),
+        ] {
+            let err = export_to_logisim(&format!("movs r1, #1\n{input}")).unwrap_err();
+            match &err {
+                ExportError::ParseError(ParseError::InvalidNumber {
+                    line,
+                    number: found,
+                }) => {
+                    assert_eq!((*line, found.as_str()), (2, number), "{input}")
+                }
+                other => panic!("unexpected error for {input}: {other}"),
+            }
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Could not parse input: Failed to parse assembly code on line 2: {message}"
+                )
+            );
+        }
+
+        assert_eq!(
+            export_to_logisim("movs r0, #0\nmovs r1, #0b101\nmovs r2, #0x1f").unwrap(),
+            export_to_logisim("movs r0, #0\nmovs r1, #5\nmovs r2, #31").unwrap()
+        );
+    }
+
+    #[test]
+    fn unaligned_offset() {
+        for (input, value) in [
+            ("ldr r0, [sp, #6]", 6),
+            ("add sp, #2", 2),
+            ("add r1, sp, #9", 9),
+        ] {
+            let err = export_to_logisim(&format!("movs r1, #1\n{input}")).unwrap_err();
+            match &err {
+                ExportError::ParseError(ParseError::UnalignedOffset {
+                    line: 2,
+                    error:
+                        ImmediateError::UnalignedOffset {
+                            value: found,
+                            alignment: 4,
+                        },
+                }) => assert_eq!(*found, value, "{input}"),
+                other => panic!("unexpected error for {input}: {other}"),
+            }
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Could not parse input: Failed to parse assembly code on line 2: \
+                     Offset of {value} bytes is not a multiple of 4 bytes"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn error_in_repetition() {
+        let input = "movs r0, #1\n.rept 2\nmovs r1, #1\nmovs r1, #256\n.endr";
+
+        match export_to_logisim(input).unwrap_err() {
+            ExportError::ParseError(err) => assert_eq!(err.line(), 4),
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn high_register_in_low_field() {
+        let complete = |instr, args| {
+            make_program(vec![ParsedLine::Instr(FullInstr { instr, args })]).map(|_| ())
+        };
+
+        let err = complete(Instr::Adds, Args::RdRnRm(Reg::R0, Reg::SP, Reg::R1)).unwrap_err();
+        assert!(matches!(
+            err,
+            CompleteError::InvalidRegister {
+                instr: Instr::Adds,
+                reg: Reg::SP
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "adds cannot use sp, only r0-r7 fit in its register fields"
+        );
+
+        assert!(matches!(
+            complete(
+                Instr::Ldr2,
+                Args::RtRnImm5(Reg::PC, Reg::R1, Immediate5::new(0).unwrap())
+            ),
+            Err(CompleteError::InvalidRegister { reg: Reg::PC, .. })
+        ));
+        assert!(complete(Instr::Adds, Args::RdRnRm(Reg::R0, Reg::R7, Reg::R1)).is_ok());
+    }
+}
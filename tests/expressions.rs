@@ -0,0 +1,38 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, LogisimProgram};
+
+    #[test]
+    fn constant_expressions() {
+        let input = "
+        movs r0, #(1<<4)
+        movs r1, #(2*3+1)";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n2010 2107";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn symbols() {
+        let input = "
+        .equ WIDTH, 12
+        .equ HEIGHT, 4
+        movs r0, #(WIDTH * HEIGHT - 1)";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n202f";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(export_to_logisim("movs r0, #(255 + 300)").is_err());
+    }
+}
@@ -1,10 +1,10 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
-    use parm_assembler::LogisimProgram;
+    use parm_assembler::{export_rom, parse_logisim, LogisimProgram};
 
-    #[test]
-    fn caesar() {
-        let input = r#"
+    const INPUT: &str = r#"
         .text
         .syntax unified
         .eabi_attribute 67, "2.09"
@@ -142,10 +142,28 @@ run:
         .eabi_attribute 30, 6
 "#;
 
+    #[test]
+    fn caesar() {
         let expected = "v2.0 raw\nb098 b0ff b0f1 2000 9002 2001 9001 e7fe 9801 9009 e7fe 9813 9007 9807 2800 d104 e7fe e7fe 2030 9008 e7fe e025 2000 9006 9005 e7fe 9805 2807 d81d e7fe 9807 210f 4008 9004 9807 0900 9007 9806 2800 d108 e7fe 9804 2800 d002 e7fe 2001 9006 e7ff e005 e7fe e7fe 9804 3030 9008 e7fe e7fe 9805 1c40 9005 e7dc e7fe e7fe 200a 9008 e7fe 9802 9901 1840 9000 9801 9002 9800 9001 e7bc";
 
-        let output = parm_assembler::export_to_logisim(input).unwrap();
+        let output = parm_assembler::export_to_logisim(INPUT).unwrap();
 
         assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
     }
+
+    #[test]
+    fn words() {
+        let rom = export_rom(INPUT).unwrap();
+        let image = parm_assembler::export_to_logisim(INPUT).unwrap().rom;
+        let expected = parse_logisim(&image).unwrap();
+
+        assert_eq!(rom.base, 0);
+        assert_eq!(rom.words().count(), 74);
+        assert!(rom
+            .words()
+            .zip(expected.into_iter().enumerate())
+            .all(|(word, expected)| word == expected));
+        assert_eq!(rom.words().nth(3), Some((3, 0x2000)));
+        assert_eq!(rom.words().last(), Some((73, 0xe7bc)));
+    }
 }
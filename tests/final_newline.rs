@@ -0,0 +1,42 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, LogisimProgram};
+
+    fn rom(words: &str) -> LogisimProgram {
+        LogisimProgram::with_rom(format!("v2.0 raw\n{words}"))
+    }
+
+    #[test]
+    fn last_instruction() {
+        let output = export_to_logisim("movs r0, #1\nmovs r1, #2\nadds r2, r0, r1").unwrap();
+
+        assert_eq!(output, rom("2001 2102 1842"));
+        assert_eq!(
+            output,
+            export_to_logisim("movs r0, #1\nmovs r1, #2\nadds r2, r0, r1\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn trailing_comment() {
+        let output = export_to_logisim("movs r0, #1\nmovs r1, #2 @ done").unwrap();
+
+        assert_eq!(output, rom("2001 2102"));
+    }
+
+    #[test]
+    fn crlf() {
+        let output = export_to_logisim("movs r0, #1\r\nmovs r1, #2").unwrap();
+
+        assert_eq!(output, rom("2001 2102"));
+    }
+
+    #[test]
+    fn branch_to_last_label() {
+        let output = export_to_logisim(".loop:\nmovs r0, #1\nb .loop").unwrap();
+
+        assert_eq!(output, rom("2001 e7fc"));
+    }
+}
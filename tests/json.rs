@@ -0,0 +1,65 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::export_to_json;
+
+    #[test]
+    fn first_instruction() {
+        let input = "
+            movs r0, #1
+            .loop:
+            b .loop";
+
+        let output = export_to_json(input).unwrap();
+
+        let expected = concat!(
+            r#"{"instructions":["#,
+            r#"{"address":0,"source":"movs r0, #1","mnemonic":"movs","encoding":"2001","operands":[{"kind":"reg","value":"r0"},{"kind":"imm","value":1}]},"#,
+            r#"{"address":1,"source":"b .loop","mnemonic":"b","encoding":"e7fd","operands":[{"kind":"imm","value":-3}]}"#,
+            r#"],"ram":[]}"#
+        );
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn ram() {
+        let input = r#"
+            ldr r0, .str
+            .str:
+            .asciz "Hi"
+        "#;
+
+        let output = export_to_json(input).unwrap();
+
+        assert!(output.contains(r#""source":"ldr r0, .str""#));
+        assert!(output.ends_with(r#""ram":["0048","0069"]}"#));
+    }
+
+    #[test]
+    fn expanded_source() {
+        let input = "
+            ldrb r1, [r0, r2] @ rewritten
+            ldr r3, =1000
+            movs r4, #1";
+
+        let output = export_to_json(input).unwrap();
+
+        let sources: Vec<_> = output
+            .split(r#""source":"#)
+            .skip(1)
+            .map(|rest| &rest[..rest.find(r#","mnemonic""#).unwrap()])
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                r#""ldrb r1, [r0, r2] @ rewritten""#,
+                r#""ldrb r1, [r0, r2] @ rewritten""#,
+                r#""ldr r3, =1000""#,
+                r#""ldr r3, =1000""#,
+                r#""movs r4, #1""#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::export_listing;
+
+    #[test]
+    fn listing() {
+        let input = "
+            movs r0, #1   @ start
+            .loop:
+            ldrb r1, [r0, r2]
+            b .loop";
+
+        let output = export_listing(input).unwrap();
+
+        let expected = "\
+0000  2001  movs r0, #1   @ start
+0001  1886  ldrb r1, [r0, r2]
+0002  6831  ldrb r1, [r0, r2]
+0003  e7fb  b .loop
+";
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn repetition() {
+        let input = "
+            .rept 2
+            movs r0, #1
+            adds r0, #2
+            .endr
+            ldr r1, =1000";
+
+        let output = export_listing(input).unwrap();
+
+        let expected = "\
+0000  2001  movs r0, #1
+0001  3002  adds r0, #2
+0002  2001  movs r0, #1
+0003  3002  adds r0, #2
+0004  2100  ldr r1, =1000
+0005  6809  ldr r1, =1000
+";
+
+        assert_eq!(output, expected);
+    }
+}
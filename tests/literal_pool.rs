@@ -0,0 +1,70 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, LogisimProgram};
+
+    #[test]
+    fn ldr_constant() {
+        let input = "
+            ldr r0, =1000
+            ldr r1, =0x12345678";
+
+        let output = export_to_logisim(input).unwrap();
+
+        // each constant is loaded from its pool entry
+        let expected = LogisimProgram {
+            rom: "v2.0 raw\n2000 6800 2104 6809".to_owned(),
+            ram: "v2.0 raw\n00e8 0003 0000 0000 0078 0056 0034 0012".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn pool_after_data() {
+        let input = r#"
+            ldr r0, .str
+            ldr r1, =1000
+            .str:
+            .asciz "Hi"
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = LogisimProgram {
+            rom: "v2.0 raw\n2000 2102 6809".to_owned(),
+            ram: "v2.0 raw\n0048 0069 00e8 0003 0000 0000".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn shared_constant() {
+        let input = "
+            ldr r0, =42
+            ldr r1, =42
+            ldr r2, =7";
+
+        let output = export_to_logisim(input).unwrap();
+
+        // both loads of 42 use the first pool word, 7 gets the second one
+        let expected = LogisimProgram {
+            rom: "v2.0 raw\n2000 6800 2100 6809 2204 6812".to_owned(),
+            ram: "v2.0 raw\n002a 0000 0000 0000 0007 0000 0000 0000".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn pc_relative() {
+        let input = "
+            ldr r0, [pc, #8]
+            ldr r7, [PC]";
+
+        let output = export_to_logisim(input).unwrap();
+        assert_eq!(output.rom, "v2.0 raw\n4802 4f00");
+    }
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        export_to_logisim, export_to_logisim_with_options, parse_logisim, ConvertOptions,
+        ExportOptions, LogisimError, LogisimProgram,
+    };
+
+    #[test]
+    fn round_trip() {
+        let output = export_to_logisim("movs r0, #1\nmovs r1, #2").unwrap();
+
+        assert_eq!(parse_logisim(&output.rom), Ok(vec![0x2001, 0x2102]));
+    }
+
+    #[test]
+    fn run_length() {
+        assert_eq!(
+            parse_logisim("v2.0 raw\n2001 3*0 e7fe"),
+            Ok(vec![0x2001, 0, 0, 0, 0xe7fe])
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse_logisim("2001"), Err(LogisimError::MissingHeader));
+        assert_eq!(
+            parse_logisim("v2.0 raw\n2001 zz"),
+            Err(LogisimError::InvalidWord("zz".to_owned()))
+        );
+    }
+
+    #[test]
+    fn files_round_trip() {
+        let dir = std::env::temp_dir().join(format!("parm_assembler_image_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = export_to_logisim("movs r0, #1\nldr r1, .msg\n.msg:\n.asciz \"Hi\"").unwrap();
+
+        program.write_to_dir(&dir, "program").unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("program.rom")).unwrap(),
+            program.rom
+        );
+        assert_eq!(
+            LogisimProgram::read_from_dir(&dir, "program").unwrap(),
+            program
+        );
+        assert!(LogisimProgram::read_from_dir(&dir, "missing").is_err());
+    }
+
+    #[test]
+    fn normalized() {
+        let program = export_to_logisim("movs r0, #10\nmovs r1, #2").unwrap();
+        let written = LogisimProgram {
+            rom: "V2.0  RAW\r\n200A\t2102 \n".to_owned(),
+            ram: "v2.0 raw\n\n".to_owned(),
+        };
+
+        assert_ne!(program, written);
+        assert_eq!(program.normalized(), written.normalized());
+        assert_eq!(program.normalized(), program);
+        assert_ne!(
+            program.normalized(),
+            LogisimProgram::with_rom("v2.0 raw\n200a 2103".to_owned()).normalized()
+        );
+    }
+
+    #[test]
+    fn addressed() {
+        let addressed = ConvertOptions {
+            addressed: true,
+            ..Default::default()
+        };
+        let options = ExportOptions {
+            rom: addressed.clone(),
+            ram: addressed,
+            ..Default::default()
+        };
+
+        let output = export_to_logisim_with_options(
+            "movs r0, #1\nmovs r1, #2\n.org 4\n.end:\nb .end",
+            &options,
+        );
+
+        let expected = LogisimProgram {
+            rom: "v3.0 hex words addressed\n0000: 2001\n0001: 2102\n0004: e7fd".to_owned(),
+            ram: "v3.0 hex words addressed".to_owned(),
+        };
+        assert_eq!(output.unwrap(), expected);
+    }
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, LogisimProgram};
+
+    #[test]
+    fn two_invocations() {
+        let input = r"
+            .macro print_char char
+            movs r0, #\char
+            str r0, [sp, #0]
+            .endm
+            print_char 72
+            print_char 105";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n2048 9000 2069 9000";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn repetition() {
+        let input = "
+            .rept 3
+            movs r0, #1
+            .endr";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n2001 2001 2001";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+}
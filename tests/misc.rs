@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
     use parm_assembler::{export_to_logisim, LogisimProgram};
@@ -15,4 +17,165 @@ mod tests {
 
         assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
     }
+
+    #[test]
+    fn reverse() {
+        let input = "
+        rev r0, r1
+        rev16 r4, r5
+        revsh r2, r3";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\nba08 ba6c bada";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn extend() {
+        let input = "
+        uxtb r0, r1
+        uxth r4, r5
+        sxtb r6, r7
+        sxth r2, r3";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\nb2c8 b2ac b27e b21a";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn halfword() {
+        let input = "
+        ldrh r0, [r1, #4]
+        strh r2, [r3]";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n8888 801a";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn it_block_ignored() {
+        let input = "
+        cmp r0, r1
+        itt eq
+        movs r0, #1
+        movs r1, #2";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n4288 2001 2102";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn hints() {
+        let input = "
+        wfi
+        wfe
+        sev
+        bkpt #0
+        bkpt #171";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\nbf30 bf20 bf40 be00 beab";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn software_interrupt() {
+        let input = "
+        svc #10
+        swi #255";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\ndf0a dfff";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn multiply_operands() {
+        let input = "
+        muls r5, r2, r5
+        muls r5, r5, r2
+        mul r5, r2, r5";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n4355 4355 4355";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn multiply_shorthand() {
+        let short = export_to_logisim("muls r2, r3").unwrap();
+        let long = export_to_logisim("muls r2, r3, r2").unwrap();
+
+        assert_eq!(short, long);
+        assert_eq!(short, LogisimProgram::with_rom("v2.0 raw\n435a".to_owned()));
+    }
+
+    #[test]
+    fn stack_address() {
+        let input = "
+        add r0, sp, #8
+        add r7, sp, #1020";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\na802 afff";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn character_literals() {
+        let input = r"
+        movs r0, #'A'
+        movs r1, #'\n'
+        cmp r2, #'z'";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n2041 210a 2a7a";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn hex_offsets() {
+        let hex = "
+            ldr r0, [sp, #0x10]
+            str r1, [sp, #0x8]
+            ldr r2, [r3, #0x4]
+            ldrh r4, [r5, #0xA]
+            add r0, sp, #0x10
+            sub sp, #0x40
+            add sp, #0X40";
+        let decimal = "
+            ldr r0, [sp, #16]
+            str r1, [sp, #8]
+            ldr r2, [r3, #4]
+            ldrh r4, [r5, #10]
+            add r0, sp, #16
+            sub sp, #64
+            add sp, #64";
+
+        let output = export_to_logisim(hex).unwrap();
+
+        assert_eq!(output.rom, "v2.0 raw\n9804 9102 691a 896c a804 b090 b010");
+        assert_eq!(output, export_to_logisim(decimal).unwrap());
+    }
 }
@@ -0,0 +1,56 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, CompleteError, ExportError, LogisimProgram};
+
+    #[test]
+    fn pads_to_address() {
+        let input = "
+        movs r0, #1
+        .org 4
+        movs r1, #2";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n2001 0000 0000 0000 2102";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn labels_follow_org() {
+        let input = "
+        b vector
+        .org 0x3
+        vector:
+        b vector";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\ne000 0000 0000 e7fd";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn backwards() {
+        let input = "
+        movs r0, #1
+        movs r1, #2
+        .org 1";
+
+        let err = export_to_logisim(input).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ExportError::CompleteError {
+                error: CompleteError::OrgBackwards {
+                    target: 1,
+                    current: 2
+                },
+                ..
+            }
+        ));
+    }
+}
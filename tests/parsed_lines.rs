@@ -0,0 +1,42 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        assemble_lines, export_to_logisim, Args, FullInstr, Immediate8, Instr, ParsedLine, Reg,
+    };
+
+    #[test]
+    fn hand_built() {
+        let lines = vec![
+            ParsedLine::Label("start".to_owned()),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Movs,
+                args: Args::RdImm8(Reg::R0, Immediate8::new(1).unwrap()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::Ldr3,
+                args: Args::RtLabel(Reg::R1, "message".to_owned()),
+            }),
+            ParsedLine::Instr(FullInstr {
+                instr: Instr::B,
+                args: Args::Label("start".to_owned()),
+            }),
+            ParsedLine::Label("message".to_owned()),
+            ParsedLine::String("Hi".to_owned()),
+        ];
+
+        let source = "
+            start:
+            movs r0, #1
+            ldr r1, message
+            b start
+            message:
+            .asciz \"Hi\"";
+
+        assert_eq!(
+            assemble_lines(lines).unwrap(),
+            export_to_logisim(source).unwrap()
+        );
+    }
+}
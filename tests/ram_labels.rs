@@ -0,0 +1,164 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        export_to_logisim, export_to_logisim_with_options, CompleteError, ExportError,
+        ExportOptions, LogisimProgram, ProgramOptions, Reg,
+    };
+
+    #[test]
+    fn label_after_two_strings() {
+        let input = r#"
+            ldr r0, .second
+            ldrb r1, [r0]
+            .first:
+            .asciz "ab"
+            .asciz "cd"
+            .second:
+            .asciz "e"
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = LogisimProgram {
+            // the second label is at the fifth word of the RAM
+            rom: "v2.0 raw\n2004 6801".to_owned(),
+            ram: "v2.0 raw\n0061 0062 0063 0064 0065".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn label_after_non_ascii_string() {
+        let input = r#"
+            ldr r0, .second
+            .first:
+            .asciz "é!"
+            .second:
+            .asciz "x"
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = LogisimProgram {
+            // "é" is two UTF-8 bytes, so it takes two words
+            rom: "v2.0 raw\n2003".to_owned(),
+            ram: "v2.0 raw\n00c3 00a9 0021 0078".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let input = r#"
+            ldr r0, .second
+            .first:
+            .asciz "\u00e9"
+            .second:
+            .asciz "x"
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = LogisimProgram {
+            rom: "v2.0 raw\n2002".to_owned(),
+            ram: "v2.0 raw\n00c3 00a9 0078".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn dotted_labels() {
+        let input = r#"
+            ldr r0, .LCPI0_0
+            ldr r1, .LCPI0_1
+            .LCPI0_0:
+            .long .L.str
+            .LCPI0_1:
+            .long .L.str.1
+            .section .rodata.str1.1,"aMS",%progbits,1
+            .L.str:
+            .asciz "ab"
+            .L.str.1:
+            .asciz "c"
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = LogisimProgram {
+            // .L.str.1 is not mistaken for .L.str
+            rom: "v2.0 raw\n2000 2102".to_owned(),
+            ram: "v2.0 raw\n0061 0062 0063".to_owned(),
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn offset_too_large() {
+        let input = format!(
+            ".table:\n.asciz \"{}\"\n.last:\n.asciz \"!\"\nldr r0, .last",
+            "a".repeat(299)
+        );
+
+        let err = export_to_logisim(&input).unwrap_err();
+
+        match &err {
+            ExportError::CompleteError {
+                error: CompleteError::RamOffsetTooLarge { label, offset },
+                ..
+            } => {
+                assert_eq!(label, ".last");
+                assert_eq!(*offset, 299);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert!(err.to_string().contains("up to 255"), "{err}");
+    }
+
+    #[test]
+    fn ram_base() {
+        let input = "ldr r0, .msg\nldr r1, =1000\n.msg:\n.asciz \"Hi\"";
+        let with_base = |ram_base| {
+            let options = ExportOptions {
+                program: ProgramOptions {
+                    ram_base,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            export_to_logisim_with_options(input, &options)
+        };
+
+        assert_eq!(
+            export_to_logisim(input).unwrap().rom,
+            "v2.0 raw\n2000 2102 6809"
+        );
+        assert_eq!(
+            with_base(Some(Reg::R7)).unwrap().rom,
+            "v2.0 raw\n2000 19c0 2102 19c9 6809"
+        );
+        assert_eq!(
+            with_base(Some(Reg::R5)).unwrap().rom,
+            "v2.0 raw\n2000 1940 2102 1949 6809"
+        );
+        assert!(matches!(
+            with_base(Some(Reg::SP)),
+            Err(ExportError::CompleteError {
+                error: CompleteError::RamBaseRegister(Reg::SP),
+                ..
+            })
+        ));
+        assert!(matches!(
+            with_base(Some(Reg::R1)),
+            Err(ExportError::CompleteError {
+                error: CompleteError::LoadIntoRamBase(Reg::R1),
+                ..
+            })
+        ));
+    }
+}
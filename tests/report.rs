@@ -0,0 +1,125 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use parm_assembler::{assemble_file, Severity};
+
+    fn fixture(name: &str, source: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("parm_assembler_report_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn warning_and_error() {
+        let path = fixture(
+            "mixed.s",
+            "movs r0, #1\nlsls r0, r1, #0\nadds r0, r9\n.end:\nb .end",
+        );
+
+        let report = assemble_file(&path);
+
+        let found: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(Severity::Warning, Some(2)), (Severity::Error, Some(3))]
+        );
+        assert!(report.has_errors());
+        assert!(report.program.is_none());
+        assert!(report.source.starts_with("movs r0, #1\n"));
+    }
+
+    #[test]
+    fn every_parse_error() {
+        let path = fixture("errors.s", "adds r0, r9\nmovs r1, #1\nfoo r2\n");
+
+        let lines: Vec<_> = assemble_file(&path)
+            .diagnostics
+            .iter()
+            .map(|d| d.line)
+            .collect();
+        assert_eq!(lines, vec![Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn missing_label() {
+        let path = fixture("label.s", "movs r0, #1\nbne .nowhere");
+
+        let report = assemble_file(&path);
+
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].line, Some(2));
+        assert!(report.diagnostics[0].message.contains(".nowhere"));
+    }
+
+    #[test]
+    fn jump_too_far() {
+        let source = format!("far:\nbeq far\n{}beq far", "movs r0, r0\n".repeat(300));
+        let path = fixture("far.s", &source);
+
+        let report = assemble_file(&path);
+
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].line, Some(303));
+    }
+
+    #[test]
+    fn errors_after_include() {
+        let lib = fixture("lib.s", "movs r0, #1\nlsls r0, r1, #0\n");
+        let path = fixture(
+            "includes.s",
+            ".include \"lib.s\"\nmovs r2, #3\nadds r0, r9\nbne .nowhere\n",
+        );
+
+        let report = assemble_file(&path);
+
+        let found: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.path.clone(), d.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Error, Some(path.clone()), Some(3)),
+                (Severity::Error, Some(path.clone()), Some(4)),
+            ]
+        );
+        assert!(report.diagnostics[0].message.contains("line 3"));
+
+        // warnings are only looked for once the program parses
+        std::fs::write(&path, ".include \"lib.s\"\n.end:\nb .end\n").unwrap();
+        let report = assemble_file(&path);
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].path, Some(lib));
+        assert_eq!(report.diagnostics[0].line, Some(2));
+    }
+
+    #[test]
+    fn success() {
+        let path = fixture("ok.s", "movs r0, #1");
+
+        let report = assemble_file(&path);
+
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(report.program.unwrap().rom, "v2.0 raw\n2001");
+    }
+
+    #[test]
+    fn unreadable() {
+        let report = assemble_file(&std::env::temp_dir().join("parm_assembler_missing.s"));
+
+        assert!(report.has_errors());
+        assert_eq!(report.diagnostics[0].line, None);
+        assert!(report.source.is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        export_to_logisim_with, export_to_logisim_with_options, ExportError, ExportOptions,
+        LogisimProgram, ProgramOptions,
+    };
+
+    const INPUT: &str = "
+        movs r0, #1
+        movs r1, #2
+        adds r2, r0, r1";
+
+    #[test]
+    fn fits() {
+        let output = export_to_logisim_with(INPUT, Some(3)).unwrap();
+
+        let expected = "v2.0 raw\n2001 2102 1842";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn overflow() {
+        let err = export_to_logisim_with(INPUT, Some(2)).unwrap_err();
+
+        assert!(matches!(err, ExportError::CompleteError { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Could not complete instruction: Program needs 3 ROM words but only 2 are available"
+        );
+    }
+
+    #[test]
+    fn unlimited() {
+        assert!(export_to_logisim_with(INPUT, None).is_ok());
+    }
+
+    #[test]
+    fn padded() {
+        let options = ExportOptions {
+            rom_size: Some(8),
+            ..Default::default()
+        };
+        let output = export_to_logisim_with_options(INPUT, &options).unwrap();
+
+        let expected = "v2.0 raw\n2001 2102 1842 0000 0000 0000 0000 0000";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn padded_with_fill() {
+        let options = ExportOptions {
+            rom_size: Some(4),
+            program: ProgramOptions {
+                fill: 0xbf00,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = export_to_logisim_with_options(INPUT, &options).unwrap();
+
+        let expected = "v2.0 raw\n2001 2102 1842 bf00";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn too_small_to_pad() {
+        let options = ExportOptions {
+            rom_size: Some(2),
+            ..Default::default()
+        };
+        let err = export_to_logisim_with_options(INPUT, &options).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Could not complete instruction: Program needs 3 ROM words but only 2 are available"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{disassemble, export_rom, parse_lines, FullInstr, ParsedLine};
+
+    /// Assembles a single instruction, returning it as parsed along with its encoding
+    fn assemble(line: &str) -> (FullInstr, u16) {
+        let instr = match &parse_lines(line).unwrap()[..] {
+            [ParsedLine::Instr(instr)] => instr.clone(),
+            other => panic!("unexpected lines for {line}: {other:?}"),
+        };
+        let words: Vec<_> = export_rom(line).unwrap().words().collect();
+        assert_eq!(words.len(), 1, "{line}");
+        (instr, words[0].1)
+    }
+
+    fn roundtrip(lines: &[&str]) {
+        for line in lines {
+            let (instr, word) = assemble(line);
+            let decoded = disassemble(word).unwrap_or_else(|| panic!("{line}: {word:04x}"));
+            assert_eq!(decoded, instr, "{line}: {word:04x}");
+            assert_eq!(decoded.encode_u16(), Some(word), "{line}");
+        }
+    }
+
+    #[test]
+    fn shift_add_sub_move() {
+        roundtrip(&[
+            "lsls r0, r1, #4",
+            "lsrs r2, r3, #31",
+            "asrs r4, r5, #1",
+            "adds r0, r1, r2",
+            "subs r3, r4, r5",
+            "adds r5, r6, #7",
+            "subs r0, r1, #2",
+            "adds r0, #200",
+            "subs r7, #1",
+            "movs r6, #179",
+            "movs r0, r1",
+        ]);
+    }
+
+    #[test]
+    fn data_processing() {
+        roundtrip(&[
+            "ands r0, r1",
+            "eors r2, r3",
+            "lsls r0, r1",
+            "lsrs r3, r1",
+            "asrs r4, r5",
+            "adcs r6, r7",
+            "sbcs r1, r0",
+            "rors r2, r4",
+            "tst r3, r5",
+            "rsbs r3, r3, #0",
+            "cmp r0, r1",
+            "cmp r2, #20",
+            "cmn r4, r6",
+            "orrs r5, r7",
+            "muls r0, r1",
+            "bics r1, r2",
+            "mvns r3, r4",
+        ]);
+    }
+
+    #[test]
+    fn load_store_and_misc() {
+        roundtrip(&[
+            "str r0, [sp, #4]",
+            "ldr r2, [sp, #1020]",
+            "ldr r1, [r3, #2]",
+            "ldr r4, [pc, #8]",
+            "strh r1, [r2, #4]",
+            "ldrh r3, [r4, #62]",
+            "add sp, #16",
+            "sub sp, #508",
+            "add r2, sp, #12",
+            "rev r0, r1",
+            "sxtb r2, r3",
+            "uxth r4, r5",
+            "wfi",
+            "sev",
+            "bkpt #3",
+        ]);
+    }
+
+    #[test]
+    fn branches() {
+        roundtrip(&["beq #-4", "bne #12", "bge #-128", "b #-3", "b #1023"]);
+    }
+
+    #[test]
+    fn shared_encodings() {
+        // `lsls rd, rm, #0` is the encoding of `movs rd, rm`
+        let (_, word) = assemble("lsls r0, r1, #0");
+        assert_eq!(disassemble(word).unwrap().to_string(), "movs r0, r1");
+        assert!(disassemble(0xffff).is_none());
+    }
+}
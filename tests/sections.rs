@@ -0,0 +1,118 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{export_to_logisim, CompleteError, ExportError, LogisimProgram};
+
+    #[test]
+    fn data_before_text() {
+        let input = r#"
+        .section .rodata.str1.1,"aMS",%progbits,1
+.L.str:
+        .asciz  "Hi"
+.L.str.1:
+        .asciz  "!"
+
+        .text
+run:
+        ldr     r0, .L.str.1
+        b       run
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected_rom = "v2.0 raw\n2002 e7fc";
+        let expected_ram = "v2.0 raw\n0048 0069 0021";
+
+        assert_eq!(
+            output,
+            LogisimProgram {
+                rom: expected_rom.to_owned(),
+                ram: expected_ram.to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn text_label_before_data_section() {
+        let input = r#"
+run:
+        .section .rodata
+msg:
+        .asciz  "A"
+        .text
+        ldr     r0, msg
+        b       run
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected_rom = "v2.0 raw\n2000 e7fc";
+        let expected_ram = "v2.0 raw\n0041";
+
+        assert_eq!(
+            output,
+            LogisimProgram {
+                rom: expected_rom.to_owned(),
+                ram: expected_ram.to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn bss_after_data() {
+        let input = r#"
+        .bss
+buffer:
+        .space 3
+        .data
+msg:
+        .asciz  "Hi"
+        .zero 1
+        .text
+run:
+        ldr     r0, buffer
+        ldr     r1, msg
+        b       run
+        "#;
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected_rom = "v2.0 raw\n2003 2100 e7fb";
+        let expected_ram = "v2.0 raw\n0048 0069 0000 0000 0000 0000";
+
+        assert_eq!(
+            output,
+            LogisimProgram {
+                rom: expected_rom.to_owned(),
+                ram: expected_ram.to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn string_in_bss() {
+        let input = ".bss\nbuffer:\n.asciz \"A\"\n.text\nrun:\nb run";
+
+        assert!(matches!(
+            export_to_logisim(input),
+            Err(ExportError::CompleteError {
+                error: CompleteError::InitializedBss(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn space_in_text() {
+        let input = "buffer:\n.space 4\nrun:\nb run";
+
+        assert!(matches!(
+            export_to_logisim(input),
+            Err(ExportError::CompleteError {
+                error: CompleteError::SpaceInText(4),
+                ..
+            })
+        ));
+    }
+}
@@ -1,6 +1,8 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
-    use parm_assembler::{export_to_logisim, LogisimProgram};
+    use parm_assembler::{export_to_logisim, parse_lines, Instr, LogisimProgram, ParsedLine};
 
     #[test]
     fn lsls() {
@@ -68,4 +70,77 @@ mod tests {
 
         assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
     }
+
+    #[test]
+    fn without_suffix() {
+        assert_eq!(
+            export_to_logisim("add r0, r1, r2").unwrap(),
+            export_to_logisim("adds r0, r1, r2").unwrap()
+        );
+        assert_eq!(
+            export_to_logisim("sub r3, r4, r5").unwrap(),
+            export_to_logisim("subs r3, r4, r5").unwrap()
+        );
+    }
+
+    #[test]
+    fn shift_by_register() {
+        let input = "
+        lsls r0, r1, #4
+        lsls r0, r1
+        lsr r2, r3
+        asrs r4, r5, #1";
+
+        let output = export_to_logisim(input).unwrap();
+
+        let expected = "v2.0 raw\n0108 4088 40da 106c";
+
+        assert_eq!(output, LogisimProgram::with_rom(expected.to_owned()));
+    }
+
+    #[test]
+    fn shift_aliases() {
+        for (alias, canonical) in [
+            ("lsl r0, r1, #2", "lsls r0, r1, #2"),
+            ("lsr r0, r1, #2", "lsrs r0, r1, #2"),
+            ("asr r0, r1, #2", "asrs r0, r1, #2"),
+            ("ror r0, r1", "rors r0, r1"),
+        ] {
+            assert_eq!(
+                export_to_logisim(alias).unwrap(),
+                export_to_logisim(canonical).unwrap(),
+                "{alias}"
+            );
+        }
+        assert_eq!(
+            export_to_logisim("lsl r0, r1, #2").unwrap().rom,
+            "v2.0 raw\n0088"
+        );
+    }
+
+    #[test]
+    fn neg() {
+        let expected = export_to_logisim("rsbs r2, r3, #0").unwrap();
+        assert_eq!(expected.rom, "v2.0 raw\n425a");
+        for input in ["neg r2, r3", "negs r2, r3", "rsbs r2, r3"] {
+            assert_eq!(export_to_logisim(input).unwrap(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn immediate_forms() {
+        for (input, instr, expected) in [
+            ("adds r0, #200", Instr::Adds3, "30c8"),
+            ("adds r0, r1, #2", Instr::Adds2, "1c88"),
+            ("subs r0, #200", Instr::Subs3, "38c8"),
+            ("subs r0, r1, #2", Instr::Subs2, "1e88"),
+        ] {
+            match &parse_lines(input).unwrap()[..] {
+                [ParsedLine::Instr(parsed)] => assert_eq!(parsed.instr, instr, "{input}"),
+                other => panic!("unexpected lines for {input}: {other:?}"),
+            }
+            let output = export_to_logisim(input).unwrap();
+            assert_eq!(output.rom, format!("v2.0 raw\n{expected}"), "{input}");
+        }
+    }
 }
@@ -1,3 +1,5 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
     use parm_assembler::{export_to_logisim, LogisimProgram};
@@ -857,7 +859,7 @@ run:
 	.eabi_attribute	30, 6
         "#;
 
-        let expected = "v2.0 raw\n1c3f b0af b0ff b0f1 2007 03c0 9019 200f 03c0 9018 9819 0a00 9918 0a09 4341 9117 e7fe 9819 9016 9816 9004 9a04 0010 0c01 9115 9815 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9816 0402 0c12 2164 434a 434a 0c13 9315 9815 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fe 2020 901f e7fe e7fe 202a 901f e7fe e7fe e7fe 2020 901f e7fe e7fe e7fe 9818 9014 9814 9003 9a03 0010 0c01 9113 9813 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9814 0402 0c12 2164 434a 434a 0c13 9313 9813 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe 203d 901f e7fe e7fe 200a 901f e7fe e7fe e7fe 9817 9012 9812 9002 9a02 0010 0c01 9111 9811 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9812 0402 0c12 2164 434a 434a 0c13 9311 9811 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fe e7fe e7fe e7fe 200a 901f e7fe e7fe 2073 901f e7fe e7fe e7fe 2071 901f e7fe e7fe e7fe 2072 901f e7fe e7fe e7fe 2074 901f e7fe e7fe e7fe 203d 901f e7fe e7fe 9817 900c 2001 0780 900d 2000 900e e7fe 980d 2841 d318 e7fe 980e 990d 1840 900f 980c 990f 4288 d308 e7fe 980f 990c 1a08 900c 980f 990d 1840 900e e7fe 980c 0040 900c 980d 0840 900d e7e1 980e 0a00 900b 980b 9010 e7fe 9810 900a 980a 9001 9a01 0010 0c01 9109 9809 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 980a 0402 0c12 2164 434a 434a 0c13 9309 9809 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fe e7fe e7fe e7fe 200a 901f e7fe e7fe 202f 901f e7fe e7fe e7fe 2031 901f e7fe e7fe e7fe 202e 901f e7fe e7fe e7fe 2035 901f e7fe e7fe e7fe 203d 901f e7fe e7fe 2003 03c0 9008 e7fe 9810 0200 9908 0004 000d 982b 9006 9806 0200 9007 9807 9000 9c00 0020 0c01 9105 9805 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9807 0402 0c12 2164 434a 434a 0c13 9305 9805 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fd";
+        let expected = "v2.0 raw\naf02 b0af b0ff b0f1 2007 03c0 9019 200f 03c0 9018 9819 0a00 9918 0a09 4341 9117 e7fe 9819 9016 9816 9004 9a04 0010 0c01 9115 9815 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9816 0402 0c12 2164 434a 434a 0c13 9315 9815 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fe 2020 901f e7fe e7fe 202a 901f e7fe e7fe e7fe 2020 901f e7fe e7fe e7fe 9818 9014 9814 9003 9a03 0010 0c01 9113 9813 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9814 0402 0c12 2164 434a 434a 0c13 9313 9813 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe 203d 901f e7fe e7fe 200a 901f e7fe e7fe e7fe 9817 9012 9812 9002 9a02 0010 0c01 9111 9811 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9812 0402 0c12 2164 434a 434a 0c13 9311 9811 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fe e7fe e7fe e7fe 200a 901f e7fe e7fe 2073 901f e7fe e7fe e7fe 2071 901f e7fe e7fe e7fe 2072 901f e7fe e7fe e7fe 2074 901f e7fe e7fe e7fe 203d 901f e7fe e7fe 9817 900c 2001 0780 900d 2000 900e e7fe 980d 2841 d318 e7fe 980e 990d 1840 900f 980c 990f 4288 d308 e7fe 980f 990c 1a08 900c 980f 990d 1840 900e e7fe 980c 0040 900c 980d 0840 900d e7e1 980e 0a00 900b 980b 9010 e7fe 9810 900a 980a 9001 9a01 0010 0c01 9109 9809 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 980a 0402 0c12 2164 434a 434a 0c13 9309 9809 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fe e7fe e7fe e7fe 200a 901f e7fe e7fe 202f 901f e7fe e7fe e7fe 2031 901f e7fe e7fe e7fe 202e 901f e7fe e7fe e7fe 2035 901f e7fe e7fe e7fe 203d 901f e7fe e7fe 2003 03c0 9008 e7fe 9810 0200 9908 0004 000d 982b 9006 9806 0200 9007 9807 9000 9c00 0020 0c01 9105 9805 9020 e7fe 982a 901e 981e 0b00 901e 2000 901d 901c e7fe 981c 2804 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe 9807 0402 0c12 2164 434a 434a 0c13 9305 9805 9020 e7fe 202e 901f e7fe e7fe 982a 901e 981e 0c00 901e 2000 901d 901c e7fe 981c 2803 d810 e7fe 981e 210f 4008 901b 981e 0900 901e e7fe 981b 3030 901f e7fe e7fe 981c 1c40 901c e7e9 e7fe e7fe e7fe e7fe e7fd";
 
         let actual = export_to_logisim(input).unwrap();
 
@@ -1,10 +1,12 @@
+#![cfg(feature = "std")]
+
 #[cfg(test)]
 mod tests {
-    use parm_assembler::{export_to_logisim, LogisimProgram};
+    use parm_assembler::{
+        export_to_logisim, make_program, parse_lines, ram_strings, LogisimProgram, RamString,
+    };
 
-    #[test]
-    fn two_strings() {
-        let input = r#"
+    const INPUT: &str = r#"
                 .text
         .syntax unified
         .eabi_attribute 67, "2.09"
@@ -125,7 +127,9 @@ run:
         .eabi_attribute 30, 6
         "#;
 
-        let actual = export_to_logisim(input).unwrap();
+    #[test]
+    fn two_strings() {
+        let actual = export_to_logisim(INPUT).unwrap();
 
         println!("{:#?}", actual);
 
@@ -144,4 +148,30 @@ run:
             }
         );
     }
+
+    #[test]
+    fn strings() {
+        let strings = ram_strings(parse_lines(INPUT).unwrap()).unwrap();
+
+        let expected = vec![
+            RamString {
+                labels: vec![".L.str".to_owned()],
+                offset: 0,
+                text: "Hello world\n".to_owned(),
+            },
+            RamString {
+                labels: vec![".L.str.1".to_owned()],
+                offset: 12,
+                text: "Goodbye!".to_owned(),
+            },
+        ];
+        assert_eq!(strings, expected);
+    }
+
+    #[test]
+    fn ram_bytes() {
+        let program = make_program(parse_lines(INPUT).unwrap()).unwrap();
+
+        assert_eq!(program.ram_bytes(), b"Hello world\nGoodbye!");
+    }
 }
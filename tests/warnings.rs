@@ -0,0 +1,51 @@
+#![cfg(feature = "std")]
+
+#[cfg(test)]
+mod tests {
+    use parm_assembler::{
+        assemble_with_warnings, assemble_with_warnings_with_options, export_to_logisim,
+        ExportOptions, LintOptions, WarningKind,
+    };
+
+    #[test]
+    fn shift_by_zero() {
+        let input = "lsls r0, r1, #0";
+        let (program, warnings) = assemble_with_warnings(input).unwrap();
+
+        assert_eq!(program, export_to_logisim(input).unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::ShiftByZero);
+        assert_eq!(
+            warnings[0].to_string(),
+            "instruction 0: shift by zero is a move"
+        );
+    }
+
+    #[test]
+    fn no_warnings() {
+        let (_, warnings) = assemble_with_warnings("movs r0, r1\nadds r0, r0, #1").unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn fall_through() {
+        let options = ExportOptions {
+            lint: LintOptions { fall_through: true },
+            ..Default::default()
+        };
+
+        let (_, warnings) =
+            assemble_with_warnings_with_options("movs r0, #1\nadds r0, r1, r2", &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::FallThrough);
+        assert_eq!(warnings[0].address, 1);
+
+        let (_, warnings) =
+            assemble_with_warnings_with_options(".loop:\nadds r0, r1, r2\nb .loop", &options)
+                .unwrap();
+        assert!(warnings.is_empty());
+
+        let (_, warnings) = assemble_with_warnings("adds r0, r1, r2").unwrap();
+        assert!(warnings.is_empty());
+    }
+}